@@ -1,26 +1,24 @@
 use crate::ipc::events::CustomEvent;
 use crate::shared::error::AppResult;
-use crate::shared::storage::general;
-use crate::shared::storage::key::StorageKey;
+use crate::shared::storage::settings::{self, Settings};
 use anyhow::Context;
 use tauri::{AppHandle, Listener};
 use tauri_plugin_autostart::ManagerExt;
 
 /// Sets up the autostart feature based on user preferences.
 ///
-/// This function reads the `Autostart` setting and enables or disables the autostart feature accordingly.
+/// This function reads the `autostart` setting and enables or disables the autostart feature
+/// accordingly, then keeps it in sync whenever the settings change.
 pub fn set_up_autostart(app_handle: &AppHandle) -> AppResult<()> {
     log::info!("Setting up autostart...");
 
-    let should_autostart =
-        general::find(StorageKey::Autostart, app_handle).map(|b| b.unwrap_or(true));
-    toggle_autostart(should_autostart, app_handle)?;
+    toggle_autostart(settings::get(app_handle)?.autostart, app_handle)?;
 
     let owned_app_handle = app_handle.to_owned();
-    app_handle.listen(CustomEvent::Autostart.to_string(), move |event| {
-        let should_autostart =
-            serde_json::from_str(event.payload()).context("Failed to deserialize autostart event");
-        let _ = toggle_autostart(should_autostart, &owned_app_handle)
+    app_handle.listen(CustomEvent::Settings.to_string(), move |event| {
+        let _ = serde_json::from_str::<Settings>(event.payload())
+            .context("Failed to deserialize settings event")
+            .and_then(|settings| toggle_autostart(settings.autostart, &owned_app_handle))
             .inspect_err(|e| log::error!("{e:?}"));
     });
 
@@ -28,8 +26,7 @@ pub fn set_up_autostart(app_handle: &AppHandle) -> AppResult<()> {
 }
 
 /// Applies the autostart changes based on user preferences.
-fn toggle_autostart(enable: AppResult<bool>, app_handle: &AppHandle) -> AppResult<()> {
-    let should_autostart = enable?;
+fn toggle_autostart(should_autostart: bool, app_handle: &AppHandle) -> AppResult<()> {
     let currently_enabled_autostart = app_handle.autolaunch().is_enabled()?;
 
     log::info!(
@@ -49,7 +46,5 @@ fn toggle_autostart(enable: AppResult<bool>, app_handle: &AppHandle) -> AppResul
         app_handle.autolaunch().disable()?;
     }
 
-    general::set(StorageKey::Autostart, should_autostart, app_handle)?;
-
     Ok(())
 }
@@ -1,9 +1,45 @@
+use crate::external::todoist::resources;
+use crate::external::todoist::task::{self, NewTask};
 use crate::shared::error::AppResult;
-use anyhow::format_err;
+#[cfg(unix)]
+use crate::shared::metadata::APP_ID;
+use anyhow::{ensure, format_err, Context};
+use serde_json::{json, Value};
+use std::env;
 use std::fmt;
+use std::io::{self, IsTerminal, Read};
+#[cfg(unix)]
+use std::os::unix::fs::DirBuilderExt;
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+#[cfg(unix)]
+use std::{process, time::Duration};
+use tauri::AppHandle;
+use tauri_plugin_cli::Matches;
 
 pub const MINIMIZE_ARG: &str = "--minimize";
 
+/// The content argument value indicating that the content should be read from stdin.
+const STDIN_CONTENT: &str = "-";
+
+/// The flags of the `add` subcommand that take a value.
+const ADD_VALUE_FLAGS: &[&str] = &[
+    "--description",
+    "-d",
+    "--project",
+    "-p",
+    "--label",
+    "-l",
+    "--priority",
+    "--due",
+];
+
+/// How long a client waits for the running instance to reply.
+#[cfg(unix)]
+const REPLY_TIMEOUT: Duration = Duration::from_secs(60);
+
 /// Represents a command-line argument that can be passed to the application.
 #[derive(Debug)]
 pub enum Argument {
@@ -33,3 +69,318 @@ impl TryFrom<&str> for Argument {
         }
     }
 }
+
+/// Represents a subcommand that can be passed to the application.
+#[derive(Debug)]
+pub enum Subcommand {
+    /// Add a task through the running instance without opening any window.
+    Add,
+}
+
+impl fmt::Display for Subcommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Subcommand::Add => write!(f, "add"),
+        }
+    }
+}
+
+impl TryFrom<&str> for Subcommand {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> AppResult<Self> {
+        match value {
+            "add" => Ok(Self::Add),
+            _ => Err(format_err!("Unknown subcommand: {}", value)),
+        }
+    }
+}
+
+/// Represents an argument of the `add` subcommand.
+#[derive(Debug)]
+enum AddArgument {
+    Content,
+    Description,
+    Project,
+    Label,
+    Priority,
+    Due,
+    Json,
+    ReplyTo,
+}
+
+impl fmt::Display for AddArgument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddArgument::Content => write!(f, "content"),
+            AddArgument::Description => write!(f, "description"),
+            AddArgument::Project => write!(f, "project"),
+            AddArgument::Label => write!(f, "label"),
+            AddArgument::Priority => write!(f, "priority"),
+            AddArgument::Due => write!(f, "due"),
+            AddArgument::Json => write!(f, "json"),
+            AddArgument::ReplyTo => write!(f, "reply-to"),
+        }
+    }
+}
+
+/// Runs the current process as a client of the running instance if it was started
+/// with the `add` subcommand, e.g., `capturist add "Buy milk tomorrow #Errands"`.
+///
+/// The arguments are forwarded to the running instance through the single-instance
+/// mechanism by spawning a second process. On Unix, the result is received through a
+/// one-shot socket; elsewhere, the client only reports that the task was forwarded.
+/// If no instance is running, the spawned process becomes the running instance and
+/// stays in the system tray.
+///
+/// Returns the exit code of the client, or `None` if the application should start as usual.
+pub fn run_add_client() -> Option<i32> {
+    let args = env::args().collect::<Vec<_>>();
+    let reply_to_flag = format!("--{}", AddArgument::ReplyTo);
+    let is_add_subcommand = args
+        .get(1)
+        .is_some_and(|arg| Subcommand::try_from(arg.as_str()).is_ok());
+    let is_forwarded = args.iter().any(|arg| arg.starts_with(&reply_to_flag));
+    if !is_add_subcommand || is_forwarded {
+        return None;
+    }
+
+    let print_json = args.contains(&format!("--{}", AddArgument::Json));
+    let exit_code = match forward_add_subcommand(args) {
+        Ok(reply) => print_reply(&reply, print_json),
+        Err(e) => {
+            eprintln!("Error: {e:#}");
+            2
+        }
+    };
+    Some(exit_code)
+}
+
+/// Handles the `add` subcommand on the running instance and sends the result
+/// back to the client that requested it.
+pub fn handle_add_subcommand(matches: &Matches, app_handle: &AppHandle) {
+    log::info!("Handling the add subcommand...");
+
+    let reply_to = get_string(matches, AddArgument::ReplyTo);
+    let new_task = get_new_task(matches);
+    let owned_app_handle = app_handle.to_owned();
+    tauri::async_runtime::spawn(async move {
+        let reply = match new_task {
+            Ok(new_task) => submit_new_task(new_task, &owned_app_handle).await,
+            Err(e) => Err(e),
+        }
+        .unwrap_or_else(|e| {
+            log::error!("{e:?}");
+            json!({ "status": "failed", "error": e.to_string() })
+        });
+
+        if let Some(reply_to) = reply_to {
+            let _ = send_reply(&reply_to, &reply).inspect_err(|e| log::error!("{e:?}"));
+        }
+    });
+}
+
+/// Submits the task of the `add` subcommand, resolving its project by name, and returns
+/// the submission as the reply.
+async fn submit_new_task(mut new_task: NewTask, app_handle: &AppHandle) -> AppResult<Value> {
+    if let Some(project) = &new_task.project_id {
+        new_task.project_id = Some(resources::find_project_id(project, app_handle).await?);
+    }
+    let submission = task::submit_task(new_task, app_handle).await?;
+    Ok(serde_json::to_value(submission)?)
+}
+
+/// Sends the result of the `add` subcommand to the socket the client listens on.
+#[cfg(unix)]
+fn send_reply(reply_to: &str, reply: &Value) -> AppResult<()> {
+    use std::io::Write;
+
+    UnixStream::connect(reply_to)
+        .and_then(|mut stream| writeln!(stream, "{reply}"))
+        .context("Failed to reply to the client")
+}
+
+/// Replies are only sent through Unix sockets, which clients elsewhere never ask for.
+#[cfg(not(unix))]
+fn send_reply(_reply_to: &str, _reply: &Value) -> AppResult<()> {
+    anyhow::bail!("Replying to the client is not supported on this platform")
+}
+
+/// Forwards the `add` subcommand to the running instance, reading the content from stdin if needed.
+fn forward_add_subcommand(mut args: Vec<String>) -> AppResult<Value> {
+    let content_index = find_content_index(&args);
+    if content_index.is_none_or(|index| args[index] == STDIN_CONTENT) {
+        ensure!(
+            !io::stdin().is_terminal(),
+            "Missing task content; pass it as an argument or through stdin"
+        );
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        let content = content.trim().to_owned();
+        match content_index {
+            Some(index) => args[index] = content,
+            None => args.extend(["--".to_owned(), content]),
+        }
+    }
+
+    forward_to_running_instance(args)
+}
+
+/// Starts a second process with the given arguments, which the single-instance
+/// mechanism hands over to the running instance.
+fn spawn_forwarding_process(args: &[String]) -> AppResult<()> {
+    // AppImages are mounted only while running, so the image itself must be started.
+    let executable = env::var_os("APPIMAGE")
+        .map(PathBuf::from)
+        .map_or_else(env::current_exe, Ok)?;
+    Command::new(executable)
+        .args(&args[1..])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to reach the running instance")?;
+    Ok(())
+}
+
+/// Forwards the arguments and waits for the reply on a socket in a directory only
+/// the current user can access, so no other user can reply or connect to it.
+#[cfg(unix)]
+fn forward_to_running_instance(mut args: Vec<String>) -> AppResult<Value> {
+    use std::sync::mpsc;
+    use std::{fs, thread};
+
+    let socket_dir = get_reply_socket_dir();
+    fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&socket_dir)
+        .context("Failed to create the reply socket directory")?;
+    let socket_path = socket_dir.join("reply.sock");
+    let result = UnixListener::bind(&socket_path)
+        .context("Failed to create the reply socket")
+        .and_then(|listener| {
+            args.insert(
+                2,
+                format!("--{}={}", AddArgument::ReplyTo, socket_path.display()),
+            );
+            spawn_forwarding_process(&args)?;
+
+            let (sender, receiver) = mpsc::channel();
+            thread::spawn(move || {
+                let reply = listener
+                    .accept()
+                    .context("Failed to receive the reply")
+                    .and_then(|(stream, _)| Ok(serde_json::from_reader::<_, Value>(stream)?));
+                let _ = sender.send(reply);
+            });
+            receiver
+                .recv_timeout(REPLY_TIMEOUT)
+                .context("Timed out waiting for the running instance to reply")?
+        });
+    let _ = fs::remove_dir_all(&socket_dir);
+
+    result
+}
+
+/// Forwards the arguments without waiting for a reply, since there is no socket to receive it.
+#[cfg(not(unix))]
+fn forward_to_running_instance(args: Vec<String>) -> AppResult<Value> {
+    spawn_forwarding_process(&args)?;
+    Ok(json!({ "status": "forwarded" }))
+}
+
+/// Prints the reply of the running instance and returns the matching exit code.
+fn print_reply(reply: &Value, print_json: bool) -> i32 {
+    let status = reply["status"].as_str().unwrap_or_default();
+    if print_json {
+        println!("{reply}");
+    } else {
+        match status {
+            "created" => println!(
+                "Task added: {}",
+                reply["task"]["url"].as_str().unwrap_or_default()
+            ),
+            "queued" => println!(
+                "Task queued; it will be added once Todoist is reachable: {}",
+                reply["reason"].as_str().unwrap_or_default()
+            ),
+            "forwarded" => println!("Task forwarded to the running instance."),
+            _ => eprintln!(
+                "Failed to add task: {}",
+                reply["error"].as_str().unwrap_or_default()
+            ),
+        }
+    }
+
+    match status {
+        "created" | "queued" | "forwarded" => 0,
+        _ => 1,
+    }
+}
+
+/// Finds the index of the positional content argument of the `add` subcommand.
+fn find_content_index(args: &[String]) -> Option<usize> {
+    let mut index = 2;
+    while index < args.len() {
+        let arg = &args[index];
+        if arg == "--" {
+            return (index + 1 < args.len()).then_some(index + 1);
+        }
+        if ADD_VALUE_FLAGS.contains(&arg.as_str()) {
+            index += 2;
+            continue;
+        }
+        if arg == STDIN_CONTENT || !arg.starts_with('-') {
+            return Some(index);
+        }
+        index += 1;
+    }
+    None
+}
+
+/// Returns the path to the directory of the reply socket, which must not exist yet.
+#[cfg(unix)]
+fn get_reply_socket_dir() -> PathBuf {
+    env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir)
+        .join(format!("{APP_ID}-add-{}", process::id()))
+}
+
+/// Builds the task to be created from the `add` subcommand matches.
+fn get_new_task(matches: &Matches) -> AppResult<NewTask> {
+    let priority = get_string(matches, AddArgument::Priority)
+        .map(|priority| priority.parse::<u8>())
+        .transpose()
+        .context("Invalid priority")?;
+    let labels = matches
+        .args
+        .get(&AddArgument::Label.to_string())
+        .and_then(|arg| arg.value.as_array())
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|value| value.as_str().map(str::to_owned))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(NewTask {
+        content: get_string(matches, AddArgument::Content).context("Missing task content")?,
+        description: get_string(matches, AddArgument::Description),
+        // The project is given by its name and only resolved when the task is submitted.
+        project_id: get_string(matches, AddArgument::Project),
+        labels,
+        priority,
+        due: get_string(matches, AddArgument::Due),
+    })
+}
+
+/// Returns the string value of an `add` subcommand argument, if present.
+fn get_string(matches: &Matches, argument: AddArgument) -> Option<String> {
+    matches
+        .args
+        .get(&argument.to_string())
+        .and_then(|arg| arg.value.as_str())
+        .map(str::to_owned)
+}
@@ -0,0 +1,646 @@
+//! This module installs a custom keybinding for opening the Quick-Add dialog in the
+//! settings of the desktop environment, for sessions where the application cannot
+//! register global shortcuts by itself.
+//!
+//! Every change is recorded, so the keybinding can be removed again later.
+
+use crate::desktop::{cli, shortcut};
+use crate::shared::environment;
+use crate::shared::error::AppResult;
+use crate::shared::metadata::APP_TITLE;
+use crate::shared::storage::general;
+use crate::shared::storage::key::StorageKey;
+use anyhow::{bail, format_err, Context};
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::{env, fmt, fs};
+use tauri::{AppHandle, Manager};
+
+/// The identifier of the keybinding, used wherever the desktop environment needs a unique name.
+const KEYBINDING_ID: &str = "capturist-quick-add";
+
+/// The schema holding the list of GNOME custom keybindings.
+const GNOME_MEDIA_KEYS_SCHEMA: &str = "org.gnome.settings-daemon.plugins.media-keys";
+
+/// The relocatable schema of a single GNOME custom keybinding.
+const GNOME_KEYBINDING_SCHEMA: &str =
+    "org.gnome.settings-daemon.plugins.media-keys.custom-keybinding";
+
+/// The dconf directory of the GNOME custom keybindings.
+const GNOME_KEYBINDINGS_PATH: &str =
+    "/org/gnome/settings-daemon/plugins/media-keys/custom-keybindings";
+
+/// The schema holding the list of Cinnamon custom keybindings.
+const CINNAMON_KEYBINDINGS_SCHEMA: &str = "org.cinnamon.desktop.keybindings";
+
+/// The relocatable schema of a single Cinnamon custom keybinding.
+const CINNAMON_KEYBINDING_SCHEMA: &str = "org.cinnamon.desktop.keybindings.custom-keybinding";
+
+/// The dconf directory of the Cinnamon custom keybindings.
+const CINNAMON_KEYBINDINGS_PATH: &str = "/org/cinnamon/desktop/keybindings/custom-keybindings";
+
+/// The xfconf channel of the XFCE keyboard shortcuts.
+const XFCE_SHORTCUTS_CHANNEL: &str = "xfce4-keyboard-shortcuts";
+
+/// Represents a desktop environment whose keybindings can be managed by the application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DesktopEnvironment {
+    Gnome,
+    Kde,
+    Xfce,
+    Cinnamon,
+}
+
+impl fmt::Display for DesktopEnvironment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DesktopEnvironment::Gnome => write!(f, "GNOME"),
+            DesktopEnvironment::Kde => write!(f, "KDE Plasma"),
+            DesktopEnvironment::Xfce => write!(f, "XFCE"),
+            DesktopEnvironment::Cinnamon => write!(f, "Cinnamon"),
+        }
+    }
+}
+
+/// Represents a keybinding installed in the settings of the desktop environment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledKeybinding {
+    /// The desktop environment the keybinding was installed for.
+    pub desktop_environment: DesktopEnvironment,
+    /// The accelerator in the format of the desktop environment, e.g., `<Control>space`.
+    pub accelerator: String,
+    /// The command run by the keybinding.
+    pub command: String,
+    /// The human-readable descriptions of the changes made to the system.
+    pub changes: Vec<String>,
+}
+
+/// Detects the current desktop environment, if it is supported.
+///
+/// This is determined by checking the `XDG_CURRENT_DESKTOP` environment variable,
+/// which may list several names, e.g., `ubuntu:GNOME`.
+pub fn detect_desktop_environment() -> Option<DesktopEnvironment> {
+    env::var("XDG_CURRENT_DESKTOP")
+        .ok()?
+        .split(':')
+        .find_map(|name| match name.to_uppercase().as_str() {
+            "GNOME" | "UNITY" | "POP" => Some(DesktopEnvironment::Gnome),
+            "KDE" => Some(DesktopEnvironment::Kde),
+            "XFCE" => Some(DesktopEnvironment::Xfce),
+            "X-CINNAMON" | "CINNAMON" => Some(DesktopEnvironment::Cinnamon),
+            _ => None,
+        })
+}
+
+/// Retrieves the installed keybinding, if any.
+pub fn find_installed(app_handle: &AppHandle) -> AppResult<Option<InstalledKeybinding>> {
+    general::find(StorageKey::DesktopKeybinding, app_handle)
+}
+
+/// Installs a keybinding for opening the Quick-Add dialog in the current desktop environment.
+///
+/// The accelerator bound to the Quick-Add dialog in the application is used.
+/// A previously installed keybinding is replaced.
+pub fn install(app_handle: &AppHandle) -> AppResult<InstalledKeybinding> {
+    let desktop_environment =
+        detect_desktop_environment().context("The desktop environment is not supported")?;
+    let accelerator = shortcut::get_global_shortcut_accelerator(app_handle)?
+        .context("No shortcut is bound to the Quick-Add dialog")?;
+    let trigger = shortcut::to_xdg_trigger(&accelerator)?;
+    let command = get_quick_add_command()?;
+    let command_line = to_shell_command_line(&command);
+    log::info!("Installing the {desktop_environment} keybinding {trigger} for {command_line:?}...");
+
+    if find_installed(app_handle)?.is_some() {
+        remove(app_handle)?;
+    }
+
+    let keybinding = match desktop_environment {
+        DesktopEnvironment::Gnome => install_gnome(&trigger, &command_line)?,
+        DesktopEnvironment::Cinnamon => install_cinnamon(&trigger, &command_line)?,
+        DesktopEnvironment::Xfce => install_xfce(&trigger, &command_line)?,
+        DesktopEnvironment::Kde => install_kde(&trigger, &command, app_handle)?,
+    };
+    general::set(StorageKey::DesktopKeybinding, &keybinding, app_handle)?;
+
+    Ok(keybinding)
+}
+
+/// Removes the installed keybinding by undoing the recorded changes.
+pub fn remove(app_handle: &AppHandle) -> AppResult<()> {
+    let Some(keybinding) = find_installed(app_handle)? else {
+        return Ok(());
+    };
+    log::info!(
+        "Removing the {} keybinding {}...",
+        keybinding.desktop_environment,
+        keybinding.accelerator
+    );
+
+    match keybinding.desktop_environment {
+        DesktopEnvironment::Gnome => remove_gnome()?,
+        DesktopEnvironment::Cinnamon => remove_cinnamon()?,
+        DesktopEnvironment::Xfce => remove_xfce(&keybinding.accelerator)?,
+        DesktopEnvironment::Kde => remove_kde(app_handle)?,
+    }
+    general::delete(StorageKey::DesktopKeybinding, app_handle)
+}
+
+/// Installs the keybinding as a GNOME custom keybinding.
+fn install_gnome(trigger: &str, command: &str) -> AppResult<InstalledKeybinding> {
+    let path = format!("{GNOME_KEYBINDINGS_PATH}/{KEYBINDING_ID}/");
+    let schema = format!("{GNOME_KEYBINDING_SCHEMA}:{path}");
+    let accelerator = to_gtk_accelerator(trigger, "<Control>");
+
+    run_host_command(
+        "gsettings",
+        &["set", &schema, "name", &quote_gvariant(APP_TITLE)],
+    )?;
+    run_host_command(
+        "gsettings",
+        &["set", &schema, "command", &quote_gvariant(command)],
+    )?;
+    run_host_command(
+        "gsettings",
+        &["set", &schema, "binding", &quote_gvariant(&accelerator)],
+    )?;
+    modify_gsettings_list(GNOME_MEDIA_KEYS_SCHEMA, "custom-keybindings", |list| {
+        if !list.contains(&path) {
+            list.push(path.to_owned());
+        }
+    })?;
+
+    Ok(InstalledKeybinding {
+        desktop_environment: DesktopEnvironment::Gnome,
+        command: command.to_owned(),
+        changes: vec![
+            format!("Created the custom keybinding {path}"),
+            format!("Added {path} to {GNOME_MEDIA_KEYS_SCHEMA} custom-keybindings"),
+        ],
+        accelerator,
+    })
+}
+
+/// Removes the GNOME custom keybinding.
+fn remove_gnome() -> AppResult<()> {
+    let path = format!("{GNOME_KEYBINDINGS_PATH}/{KEYBINDING_ID}/");
+    let schema = format!("{GNOME_KEYBINDING_SCHEMA}:{path}");
+
+    modify_gsettings_list(GNOME_MEDIA_KEYS_SCHEMA, "custom-keybindings", |list| {
+        list.retain(|item| item != &path)
+    })?;
+    for key in ["name", "command", "binding"] {
+        run_host_command("gsettings", &["reset", &schema, key])?;
+    }
+    Ok(())
+}
+
+/// Installs the keybinding as a Cinnamon custom keybinding.
+fn install_cinnamon(trigger: &str, command: &str) -> AppResult<InstalledKeybinding> {
+    let schema =
+        format!("{CINNAMON_KEYBINDING_SCHEMA}:{CINNAMON_KEYBINDINGS_PATH}/{KEYBINDING_ID}/");
+    let accelerator = to_gtk_accelerator(trigger, "<Control>");
+
+    run_host_command(
+        "gsettings",
+        &["set", &schema, "name", &quote_gvariant(APP_TITLE)],
+    )?;
+    run_host_command(
+        "gsettings",
+        &["set", &schema, "command", &quote_gvariant(command)],
+    )?;
+    run_host_command(
+        "gsettings",
+        &[
+            "set",
+            &schema,
+            "binding",
+            &format!("[{}]", quote_gvariant(&accelerator)),
+        ],
+    )?;
+    modify_gsettings_list(CINNAMON_KEYBINDINGS_SCHEMA, "custom-list", |list| {
+        if !list.iter().any(|item| item == KEYBINDING_ID) {
+            list.push(KEYBINDING_ID.to_owned());
+        }
+    })?;
+
+    Ok(InstalledKeybinding {
+        desktop_environment: DesktopEnvironment::Cinnamon,
+        command: command.to_owned(),
+        changes: vec![
+            format!("Created the custom keybinding {CINNAMON_KEYBINDINGS_PATH}/{KEYBINDING_ID}/"),
+            format!("Added {KEYBINDING_ID} to {CINNAMON_KEYBINDINGS_SCHEMA} custom-list"),
+        ],
+        accelerator,
+    })
+}
+
+/// Removes the Cinnamon custom keybinding.
+fn remove_cinnamon() -> AppResult<()> {
+    let schema =
+        format!("{CINNAMON_KEYBINDING_SCHEMA}:{CINNAMON_KEYBINDINGS_PATH}/{KEYBINDING_ID}/");
+
+    modify_gsettings_list(CINNAMON_KEYBINDINGS_SCHEMA, "custom-list", |list| {
+        list.retain(|item| item != KEYBINDING_ID)
+    })?;
+    for key in ["name", "command", "binding"] {
+        run_host_command("gsettings", &["reset", &schema, key])?;
+    }
+    Ok(())
+}
+
+/// Installs the keybinding as an XFCE custom command shortcut.
+///
+/// XFCE identifies custom shortcuts by their accelerator, so an existing shortcut
+/// with the same accelerator is refused rather than overwritten.
+fn install_xfce(trigger: &str, command: &str) -> AppResult<InstalledKeybinding> {
+    let accelerator = to_gtk_accelerator(trigger, "<Primary>");
+    let property = format!("/commands/custom/{accelerator}");
+
+    let existing = run_host_command(
+        "xfconf-query",
+        &["--channel", XFCE_SHORTCUTS_CHANNEL, "--property", &property],
+    );
+    if let Ok(existing) = existing {
+        bail!("{accelerator} is already bound to {existing:?} in XFCE");
+    }
+    run_host_command(
+        "xfconf-query",
+        &[
+            "--channel",
+            XFCE_SHORTCUTS_CHANNEL,
+            "--property",
+            &property,
+            "--create",
+            "--type",
+            "string",
+            "--set",
+            command,
+        ],
+    )?;
+
+    Ok(InstalledKeybinding {
+        desktop_environment: DesktopEnvironment::Xfce,
+        command: command.to_owned(),
+        changes: vec![format!(
+            "Created the property {property} in the {XFCE_SHORTCUTS_CHANNEL} channel"
+        )],
+        accelerator,
+    })
+}
+
+/// Removes the XFCE custom command shortcut.
+fn remove_xfce(accelerator: &str) -> AppResult<()> {
+    run_host_command(
+        "xfconf-query",
+        &[
+            "--channel",
+            XFCE_SHORTCUTS_CHANNEL,
+            "--property",
+            &format!("/commands/custom/{accelerator}"),
+            "--reset",
+        ],
+    )?;
+    Ok(())
+}
+
+/// Installs the keybinding as a KDE Plasma global shortcut launching a desktop entry.
+///
+/// kglobalaccel reads its configuration on startup, so the keybinding may only
+/// take effect after signing in again.
+fn install_kde(
+    trigger: &str,
+    command: &[String],
+    app_handle: &AppHandle,
+) -> AppResult<InstalledKeybinding> {
+    let accelerator = to_qt_accelerator(trigger);
+    let desktop_file = get_kde_desktop_file_path(app_handle)?;
+    fs::create_dir_all(desktop_file.parent().context("Invalid desktop file path")?)?;
+    fs::write(&desktop_file, get_kde_desktop_entry(command))
+        .context("Failed to write the desktop entry")?;
+
+    let (groups, value) = get_kde_shortcut_entry(&accelerator);
+    let mut args = vec!["--file", "kglobalshortcutsrc"];
+    groups
+        .iter()
+        .for_each(|group| args.extend(["--group", group.as_str()]));
+    args.extend(["--key", "_launch", &value]);
+    run_host_command(get_kwriteconfig()?, &args)?;
+
+    Ok(InstalledKeybinding {
+        desktop_environment: DesktopEnvironment::Kde,
+        command: to_shell_command_line(command),
+        changes: vec![
+            format!("Created the desktop entry {}", desktop_file.display()),
+            format!(
+                "Added the _launch shortcut to the {} group of kglobalshortcutsrc",
+                groups.join("/")
+            ),
+        ],
+        accelerator,
+    })
+}
+
+/// Removes the KDE Plasma global shortcut and its desktop entry.
+fn remove_kde(app_handle: &AppHandle) -> AppResult<()> {
+    let (groups, _) = get_kde_shortcut_entry("");
+    let mut args = vec!["--file", "kglobalshortcutsrc"];
+    groups
+        .iter()
+        .for_each(|group| args.extend(["--group", group.as_str()]));
+    args.extend(["--key", "_launch", "--delete"]);
+    run_host_command(get_kwriteconfig()?, &args)?;
+
+    let desktop_file = get_kde_desktop_file_path(app_handle)?;
+    if desktop_file.exists() {
+        fs::remove_file(desktop_file).context("Failed to remove the desktop entry")?;
+    }
+    Ok(())
+}
+
+/// Returns the desktop entry launching the given command for the KDE Plasma global shortcut.
+fn get_kde_desktop_entry(command: &[String]) -> String {
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name={APP_TITLE} Quick-Add\n\
+         Exec={}\n\
+         NoDisplay=true\n\
+         X-KDE-GlobalAccel-CommandShortcut=true\n",
+        to_desktop_entry_exec(command)
+    )
+}
+
+/// Returns the path of the desktop entry launched by the KDE Plasma global shortcut.
+fn get_kde_desktop_file_path(app_handle: &AppHandle) -> AppResult<std::path::PathBuf> {
+    Ok(app_handle
+        .path()
+        .data_dir()?
+        .join("applications")
+        .join(format!("{KEYBINDING_ID}.desktop")))
+}
+
+/// Returns the kglobalshortcutsrc groups and value of the shortcut entry,
+/// whose layout differs between Plasma 5 and Plasma 6.
+fn get_kde_shortcut_entry(accelerator: &str) -> (Vec<String>, String) {
+    let desktop_file_name = format!("{KEYBINDING_ID}.desktop");
+    if env::var("KDE_SESSION_VERSION").is_ok_and(|version| version == "5") {
+        (
+            vec![desktop_file_name],
+            format!("{accelerator},none,{APP_TITLE} Quick-Add"),
+        )
+    } else {
+        (
+            vec!["services".to_owned(), desktop_file_name],
+            accelerator.to_owned(),
+        )
+    }
+}
+
+/// Returns the available `kwriteconfig` executable.
+fn get_kwriteconfig() -> AppResult<&'static str> {
+    ["kwriteconfig6", "kwriteconfig5"]
+        .into_iter()
+        .find(|program| run_host_command("which", &[program]).is_ok())
+        .context("kwriteconfig is not available")
+}
+
+/// Applies a modification to a string-list gsettings key.
+fn modify_gsettings_list(
+    schema: &str,
+    key: &str,
+    f: impl FnOnce(&mut Vec<String>),
+) -> AppResult<()> {
+    let output = run_host_command("gsettings", &["get", schema, key])?;
+    let mut list = output
+        .trim_start_matches("@as")
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(|item| item.trim().trim_matches('\'').to_owned())
+        .filter(|item| !item.is_empty())
+        .collect::<Vec<_>>();
+    f(&mut list);
+    let value = format!(
+        "[{}]",
+        list.iter()
+            .map(|item| quote_gvariant(item))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    run_host_command("gsettings", &["set", schema, key, &value])?;
+    Ok(())
+}
+
+/// Converts an XDG shortcuts trigger to a GTK accelerator, e.g., `CTRL+space` to `<Control>space`.
+fn to_gtk_accelerator(trigger: &str, control: &str) -> String {
+    trigger
+        .split('+')
+        .map(|part| match part {
+            "CTRL" => control.to_owned(),
+            "ALT" => "<Alt>".to_owned(),
+            "SHIFT" => "<Shift>".to_owned(),
+            "LOGO" => "<Super>".to_owned(),
+            key => key.to_owned(),
+        })
+        .collect()
+}
+
+/// Converts an XDG shortcuts trigger to a Qt accelerator, e.g., `CTRL+space` to `Ctrl+Space`.
+fn to_qt_accelerator(trigger: &str) -> String {
+    trigger
+        .split('+')
+        .map(|part| match part {
+            "CTRL" => "Ctrl".to_owned(),
+            "ALT" => "Alt".to_owned(),
+            "SHIFT" => "Shift".to_owned(),
+            "LOGO" => "Meta".to_owned(),
+            key => {
+                let mut chars = key.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+/// Returns the command line opening the Quick-Add dialog, as run by the desktop environment.
+pub fn get_quick_add_command_line() -> AppResult<String> {
+    Ok(to_shell_command_line(&get_quick_add_command()?))
+}
+
+/// Returns the program and the arguments opening the Quick-Add dialog.
+fn get_quick_add_command() -> AppResult<Vec<String>> {
+    let quick_add_argument = format!("--{}", cli::Argument::QuickAdd);
+    if environment::is_running_as_flatpak() {
+        let flatpak_id = env::var("FLATPAK_ID")?;
+        return Ok(vec![
+            "flatpak".to_owned(),
+            "run".to_owned(),
+            flatpak_id,
+            quick_add_argument,
+        ]);
+    }
+    // The executable of a Snap lies in the directory of the current revision,
+    // which is replaced on every refresh, so the launcher of the Snap must be used.
+    if environment::is_running_as_snap() {
+        let snap_name = env::var("SNAP_INSTANCE_NAME")?;
+        return Ok(vec![format!("/snap/bin/{snap_name}"), quick_add_argument]);
+    }
+
+    // AppImages are mounted only while running, so the image itself must be started.
+    let executable = env::var_os("APPIMAGE")
+        .map(std::path::PathBuf::from)
+        .map_or_else(env::current_exe, Ok)?;
+    let executable = executable
+        .to_str()
+        .context("The executable path is not valid UTF-8")?;
+    Ok(vec![executable.to_owned(), quick_add_argument])
+}
+
+/// Joins a command into a command line as parsed by the shell, quoting the arguments
+/// containing special characters with single quotes.
+fn to_shell_command_line(command: &[String]) -> String {
+    command
+        .iter()
+        .map(|argument| {
+            if !argument.is_empty()
+                && argument
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./=:@+,%".contains(c))
+            {
+                argument.to_owned()
+            } else {
+                format!("'{}'", argument.replace('\'', "'\\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Joins a command into the value of the `Exec` key of a desktop entry.
+///
+/// The arguments containing reserved characters are quoted with double quotes, as the
+/// Desktop Entry Specification requires, and the value is then escaped as a string value.
+fn to_desktop_entry_exec(command: &[String]) -> String {
+    command
+        .iter()
+        .map(|argument| {
+            let argument = argument.replace('%', "%%");
+            if !argument.is_empty()
+                && !argument
+                    .contains(|c: char| c.is_whitespace() || "\"'\\><~|&;$*?#()`".contains(c))
+            {
+                argument
+            } else {
+                let mut quoted = String::from('"');
+                for c in argument.chars() {
+                    if "\"`$\\".contains(c) {
+                        quoted.push('\\');
+                    }
+                    quoted.push(c);
+                }
+                quoted.push('"');
+                quoted
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('\\', "\\\\")
+}
+
+/// Quotes a string as a GVariant string literal.
+fn quote_gvariant(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Runs a command on the host system and returns its trimmed standard output.
+///
+/// In Flatpak, the command is run outside the sandbox through `flatpak-spawn`,
+/// which requires the `org.freedesktop.Flatpak` talk permission.
+fn run_host_command(program: &str, args: &[&str]) -> AppResult<String> {
+    let mut command = if environment::is_running_as_flatpak() {
+        let mut command = Command::new("flatpak-spawn");
+        command.arg("--host").arg(program);
+        command
+    } else {
+        Command::new(program)
+    };
+    let output = command
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to execute {program}"))?;
+    if !output.status.success() {
+        return Err(format_err!(
+            "{program} failed with status {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(arguments: &[&str]) -> Vec<String> {
+        arguments
+            .iter()
+            .map(|argument| argument.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn quotes_the_desktop_entry_exec_per_the_specification() {
+        let entry = get_kde_desktop_entry(&command(&[
+            "/home/me/My Apps/Capturist.AppImage",
+            "--quick-add",
+        ]));
+        assert_eq!(
+            entry,
+            format!(
+                "[Desktop Entry]\n\
+                 Type=Application\n\
+                 Name={APP_TITLE} Quick-Add\n\
+                 Exec=\"/home/me/My Apps/Capturist.AppImage\" --quick-add\n\
+                 NoDisplay=true\n\
+                 X-KDE-GlobalAccel-CommandShortcut=true\n"
+            )
+        );
+    }
+
+    #[test]
+    fn escapes_the_reserved_characters_of_the_desktop_entry_exec() {
+        assert_eq!(
+            to_desktop_entry_exec(&command(&["/opt/a \"b\" `c` $d \\e 100%", "--quick-add"])),
+            r#""/opt/a \\"b\\" \\`c\\` \\$d \\\\e 100%%" --quick-add"#
+        );
+        assert_eq!(
+            to_desktop_entry_exec(&command(&[
+                "flatpak",
+                "run",
+                "app.capturist",
+                "--quick-add"
+            ])),
+            "flatpak run app.capturist --quick-add"
+        );
+    }
+
+    #[test]
+    fn quotes_the_shell_command_line() {
+        assert_eq!(
+            to_shell_command_line(&command(&["/home/me/it's here/capturist", "--quick-add"])),
+            r"'/home/me/it'\''s here/capturist' --quick-add"
+        );
+        assert_eq!(
+            to_shell_command_line(&command(&["/snap/bin/capturist", "--quick-add"])),
+            "/snap/bin/capturist --quick-add"
+        );
+    }
+}
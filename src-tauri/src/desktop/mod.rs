@@ -2,6 +2,11 @@
 
 pub mod autostart;
 pub mod cli;
+pub mod keybinding;
+pub mod notification;
+pub mod portal;
+pub mod power;
+pub mod secret_service;
 pub mod shortcut;
 pub mod tray;
 pub mod update;
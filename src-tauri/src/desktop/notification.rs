@@ -0,0 +1,442 @@
+//! This module sends desktop notifications through the `org.freedesktop.Notifications`
+//! D-Bus interface, so they can carry action buttons, and notifications about the same
+//! task replace each other instead of stacking up.
+//!
+//! If the session bus is not available, it falls back to `notify-send`, without actions.
+//!
+//! See: https://specifications.freedesktop.org/notification-spec/latest/
+
+use crate::external::todoist::task::{self, NewTask};
+use crate::ipc::events::CustomEvent;
+use crate::shared::error::AppResult;
+use crate::shared::metadata::{APP_ID, APP_TITLE};
+use crate::shared::state::AppState;
+use crate::shared::storage::settings;
+use crate::window;
+use anyhow::{format_err, Context};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::process::Command;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Listener, Manager};
+use tauri_plugin_opener::OpenerExt;
+use tokio::sync::Mutex;
+use zbus::zvariant::Value;
+use zbus::Connection;
+
+/// The D-Bus client, created on the first notification.
+static CLIENT: Mutex<Option<NotificationClient>> = Mutex::const_new(None);
+
+#[zbus::proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+
+    fn get_capabilities(&self) -> zbus::Result<Vec<String>>;
+
+    #[zbus(signal)]
+    fn action_invoked(&self, id: u32, action_key: String) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn notification_closed(&self, id: u32, reason: u32) -> zbus::Result<()>;
+}
+
+/// Represents an action button of a notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NotificationAction {
+    /// Open the added task in Todoist.
+    OpenInTodoist,
+    /// Undo adding the task.
+    Undo,
+    /// Open the Quick-Add dialog with the task, e.g., to fix and retry a failed one.
+    Edit,
+}
+
+impl fmt::Display for NotificationAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotificationAction::OpenInTodoist => write!(f, "open-in-todoist"),
+            NotificationAction::Undo => write!(f, "undo"),
+            NotificationAction::Edit => write!(f, "edit"),
+        }
+    }
+}
+
+impl TryFrom<&str> for NotificationAction {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> AppResult<Self> {
+        match value {
+            "open-in-todoist" => Ok(Self::OpenInTodoist),
+            "undo" => Ok(Self::Undo),
+            "edit" => Ok(Self::Edit),
+            _ => Err(format_err!("Unknown notification action: {}", value)),
+        }
+    }
+}
+
+impl NotificationAction {
+    /// Returns the label of the action button.
+    fn label(&self) -> &'static str {
+        match self {
+            NotificationAction::OpenInTodoist => "Open in Todoist",
+            NotificationAction::Undo => "Undo",
+            NotificationAction::Edit => "Edit",
+        }
+    }
+}
+
+/// Represents the task a notification is about, passed on to the action handlers.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationContext {
+    /// The ID of the created task.
+    pub task_id: Option<String>,
+    /// The URL of the created task in the Todoist web app.
+    pub task_url: Option<String>,
+    /// The task as it was captured.
+    pub new_task: Option<NewTask>,
+}
+
+/// Represents an action invoked by the user, as emitted with the `NotificationAction` event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvokedAction {
+    pub action: NotificationAction,
+    pub context: NotificationContext,
+}
+
+/// Represents the connection to the notification server.
+struct NotificationClient {
+    proxy: NotificationsProxy<'static>,
+    /// Whether the server supports action buttons.
+    supports_actions: bool,
+    /// The IDs of the shown notifications about a task, keyed by the ID of the task,
+    /// so that the next notification about the same task replaces it.
+    task_notification_ids: HashMap<String, u32>,
+    /// The contexts of the notifications with actions, keyed by their IDs.
+    contexts: HashMap<u32, NotificationContext>,
+    /// The tasks routing the signals of the server, stopped along with the client.
+    signal_tasks: Vec<JoinHandle<()>>,
+}
+
+impl Drop for NotificationClient {
+    fn drop(&mut self) {
+        self.signal_tasks.iter().for_each(JoinHandle::abort);
+    }
+}
+
+/// Sends a desktop notification, replacing the previous one about the same task
+/// if it is still shown.
+///
+/// When one of the actions is invoked, it is handled and emitted with the
+/// `NotificationAction` event along with the context.
+pub async fn send(
+    title: &str,
+    body: &str,
+    actions: &[NotificationAction],
+    context: NotificationContext,
+    app_handle: &AppHandle,
+) -> AppResult<()> {
+    let result = send_via_dbus(title, body, actions, context, app_handle).await;
+    if let Err(e) = result {
+        log::warn!("Falling back to notify-send: {e:?}");
+        send_via_notify_send(title, body)?;
+    }
+    Ok(())
+}
+
+/// Sends a notification through the D-Bus interface, connecting to it on first use.
+///
+/// If the existing connection fails, e.g., because the notification server restarted,
+/// it is replaced by a new one once before giving up.
+async fn send_via_dbus(
+    title: &str,
+    body: &str,
+    actions: &[NotificationAction],
+    context: NotificationContext,
+    app_handle: &AppHandle,
+) -> AppResult<()> {
+    let mut client = CLIENT.lock().await;
+    if let Some(connected_client) = client.as_mut() {
+        let result = notify(connected_client, title, body, actions, context.to_owned()).await;
+        let Err(e) = result else {
+            return Ok(());
+        };
+        log::warn!("Reconnecting to the notification server: {e:?}");
+        *client = None;
+    }
+    let client = client.insert(connect(app_handle).await?);
+    notify(client, title, body, actions, context).await
+}
+
+/// Sends a notification with the given client and records its context.
+async fn notify(
+    client: &mut NotificationClient,
+    title: &str,
+    body: &str,
+    actions: &[NotificationAction],
+    context: NotificationContext,
+) -> AppResult<()> {
+    let actions = actions
+        .iter()
+        .filter(|_| client.supports_actions)
+        .flat_map(|action| [action.to_string(), action.label().to_owned()])
+        .collect::<Vec<_>>();
+    let replaces_id = context
+        .task_id
+        .as_ref()
+        .and_then(|task_id| client.task_notification_ids.get(task_id))
+        .copied()
+        .unwrap_or(0);
+    let hints = HashMap::from([
+        ("desktop-entry", Value::from(APP_ID)),
+        ("transient", Value::from(actions.is_empty())),
+    ]);
+    let id = client
+        .proxy
+        .notify(
+            APP_TITLE,
+            replaces_id,
+            APP_ID,
+            title,
+            body,
+            &actions.iter().map(String::as_str).collect::<Vec<_>>(),
+            hints,
+            -1,
+        )
+        .await
+        .context("Failed to send the notification")?;
+
+    client.contexts.remove(&replaces_id);
+    if let Some(task_id) = &context.task_id {
+        client.task_notification_ids.insert(task_id.to_owned(), id);
+    }
+    if !actions.is_empty() {
+        client.contexts.insert(id, context);
+    }
+    Ok(())
+}
+
+/// Connects to the notification server and starts routing the invoked actions.
+async fn connect(app_handle: &AppHandle) -> AppResult<NotificationClient> {
+    let connection = Connection::session()
+        .await
+        .context("Failed to connect to the D-Bus session bus")?;
+    let proxy = NotificationsProxy::new(&connection).await?;
+    let supports_actions = proxy
+        .get_capabilities()
+        .await
+        .context("The notification server is not available")?
+        .iter()
+        .any(|capability| capability == "actions");
+
+    let mut invocations = proxy.receive_action_invoked().await?;
+    let owned_app_handle = app_handle.to_owned();
+    let invocation_task = tauri::async_runtime::spawn(async move {
+        while let Some(invocation) = invocations.next().await {
+            let _ = on_action_invoked(invocation, &owned_app_handle)
+                .await
+                .inspect_err(|e| log::error!("{e:?}"));
+        }
+    });
+
+    let mut closures = proxy.receive_notification_closed().await?;
+    let closure_task = tauri::async_runtime::spawn(async move {
+        while let Some(closure) = closures.next().await {
+            if let Ok(args) = closure.args() {
+                if let Some(client) = CLIENT.lock().await.as_mut() {
+                    client.contexts.remove(&args.id);
+                    client.task_notification_ids.retain(|_, id| *id != args.id);
+                }
+            }
+        }
+    });
+
+    Ok(NotificationClient {
+        proxy,
+        supports_actions,
+        task_notification_ids: HashMap::new(),
+        contexts: HashMap::new(),
+        signal_tasks: vec![invocation_task, closure_task],
+    })
+}
+
+/// Handles an action invoked on one of the application's notifications.
+async fn on_action_invoked(invocation: ActionInvoked, app_handle: &AppHandle) -> AppResult<()> {
+    let args = invocation.args()?;
+    let Some(context) = CLIENT
+        .lock()
+        .await
+        .as_mut()
+        .and_then(|client| client.contexts.remove(&args.id))
+    else {
+        // The notification belongs to another application.
+        return Ok(());
+    };
+    let action = NotificationAction::try_from(args.action_key.as_str())?;
+    log::info!("Notification action invoked: {action}");
+
+    match action {
+        NotificationAction::OpenInTodoist => {
+            let url = context.task_url.as_deref().context("Missing task URL")?;
+            app_handle.opener().open_url(url, None::<&str>)?;
+        }
+        NotificationAction::Edit => {
+            // The Quick-Add window may only be created now and miss the event,
+            // so it fetches the task once mounted.
+            *app_handle.state::<AppState>().pending_edit.lock().unwrap() =
+                context.new_task.to_owned();
+            window::init_quick_add_dialog(app_handle, false)?;
+        }
+        NotificationAction::Undo => spawn_undo_and_notify(context.task_id.to_owned(), app_handle),
+    }
+    app_handle.emit(
+        &CustomEvent::NotificationAction.to_string(),
+        InvokedAction { action, context },
+    )?;
+
+    Ok(())
+}
+
+/// Takes the task to be edited in the Quick-Add dialog after the `Edit` action was invoked, if any.
+pub fn take_pending_edit(app_handle: &AppHandle) -> Option<NewTask> {
+    app_handle
+        .state::<AppState>()
+        .pending_edit
+        .lock()
+        .unwrap()
+        .take()
+}
+
+/// Notifies the user the first time a secret is kept in the encrypted fallback store,
+/// and the first time the general store is found corrupted, since no window may be open
+/// to show the warnings.
+pub fn set_up_storage_warnings(app_handle: &AppHandle) {
+    log::info!("Setting up the storage warnings...");
+
+    notify_once(
+        CustomEvent::SecretStorageFallback,
+        "Secret store unavailable",
+        "The secret store is not available, so your Todoist session is kept \
+            in an encrypted file instead. Enable strict secrets in the settings to forbid it.",
+        app_handle,
+    );
+    notify_once(
+        CustomEvent::StorageCorruption,
+        "Settings could not be read",
+        "Some of your settings could not be read and have been ignored. \
+            A backup of the old file was saved next to it.",
+        app_handle,
+    );
+}
+
+/// Sends a notification without actions the first time the given event is emitted.
+fn notify_once(
+    event: CustomEvent,
+    title: &'static str,
+    body: &'static str,
+    app_handle: &AppHandle,
+) {
+    let owned_app_handle = app_handle.to_owned();
+    app_handle.once(event.to_string(), move |_| {
+        tauri::async_runtime::spawn(async move {
+            let _ = send(
+                title,
+                body,
+                &[],
+                NotificationContext::default(),
+                &owned_app_handle,
+            )
+            .await
+            .inspect_err(|e| log::error!("{e:?}"));
+        });
+    });
+}
+
+/// Undoes a created task in the background, or the most recent one if no ID is given,
+/// and notifies the user about the result if they want to.
+pub fn spawn_undo_and_notify(task_id: Option<String>, app_handle: &AppHandle) {
+    let owned_app_handle = app_handle.to_owned();
+    tauri::async_runtime::spawn(async move {
+        let _ = undo_and_notify(task_id, &owned_app_handle)
+            .await
+            .inspect_err(|e| log::error!("{e:?}"));
+    });
+}
+
+/// Undoes a created task and notifies the user about the result.
+async fn undo_and_notify(task_id: Option<String>, app_handle: &AppHandle) -> AppResult<()> {
+    let result = match &task_id {
+        Some(task_id) => task::undo_task(task_id, app_handle)
+            .await
+            .map(|()| task_id.to_owned()),
+        None => task::undo_last_task(app_handle).await,
+    };
+    if let Err(e) = &result {
+        log::error!("{e:?}");
+    }
+
+    let settings = settings::get(app_handle)?;
+    match result {
+        Ok(task_id) if settings.notify_on_success => {
+            let body = "The task was deleted from Todoist.";
+            let context = NotificationContext {
+                task_id: Some(task_id),
+                ..NotificationContext::default()
+            };
+            send("Task removed", body, &[], context, app_handle).await
+        }
+        Err(e) if settings.notify_on_failure => {
+            let body = format!("{e:#}");
+            let context = NotificationContext {
+                task_id,
+                ..NotificationContext::default()
+            };
+            send("Failed to undo task", &body, &[], context, app_handle).await
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Sends a notification using `notify-send`.
+///
+/// Note: The Tauri notifications plugin is not used due to an issue where it
+/// does not open notifications on newer Gnome versions
+///
+/// See: https://github.com/tauri-apps/plugins-workspace/issues/2566
+fn send_via_notify_send(title: &str, body: &str) -> AppResult<()> {
+    Command::new("notify-send")
+        .arg(title)
+        .arg(body)
+        .arg("--app-name")
+        .arg(APP_ID)
+        .arg("--hint=int:transient:1")
+        .status()
+        .context("Failed to execute notify-send command")
+        .and_then(|status| {
+            if status.success() {
+                Ok(())
+            } else {
+                Err(format_err!(
+                    "notify-send command failed with status: {status:#?}"
+                ))
+            }
+        })
+}
@@ -0,0 +1,557 @@
+//! This module provides a client for the `org.freedesktop.portal.GlobalShortcuts` D-Bus
+//! interface, which is the only way for applications to register global shortcuts on Wayland.
+//!
+//! The shortcuts are bound to a session; the desktop environment asks the user to confirm
+//! them and may assign different triggers than the preferred ones.
+//!
+//! A missing portal and a request dismissed by the user are reported as a `PortalFailure`,
+//! so the callers can tell them apart from the other errors.
+//!
+//! See: https://flatpak.github.io/xdg-desktop-portal/docs/doc-org.freedesktop.portal.GlobalShortcuts.html
+
+use crate::shared::error::AppResult;
+use anyhow::{bail, format_err, Context};
+use futures_util::StreamExt;
+use rand::RngExt;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use tauri::async_runtime::{self, JoinHandle};
+use tokio::sync::Mutex;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+use zbus::Connection;
+
+/// The response code of a request that succeeded.
+const RESPONSE_SUCCESS: u32 = 0;
+
+/// The response code of a request that was cancelled by the user.
+const RESPONSE_CANCELLED: u32 = 1;
+
+/// The session currently holding the bound shortcuts, if any.
+static SESSION: Mutex<Option<PortalSession>> = Mutex::const_new(None);
+
+#[zbus::proxy(
+    interface = "org.freedesktop.portal.GlobalShortcuts",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+trait GlobalShortcuts {
+    fn create_session(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<OwnedObjectPath>;
+
+    fn bind_shortcuts(
+        &self,
+        session_handle: &OwnedObjectPath,
+        shortcuts: &[(&str, HashMap<&str, Value<'_>>)],
+        parent_window: &str,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(signal)]
+    fn activated(
+        &self,
+        session_handle: OwnedObjectPath,
+        shortcut_id: String,
+        timestamp: u64,
+        options: HashMap<String, OwnedValue>,
+    ) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn version(&self) -> zbus::Result<u32>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.portal.Request",
+    default_service = "org.freedesktop.portal.Desktop"
+)]
+trait Request {
+    #[zbus(signal)]
+    fn response(&self, response: u32, results: HashMap<String, OwnedValue>) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.portal.Session",
+    default_service = "org.freedesktop.portal.Desktop"
+)]
+trait Session {
+    fn close(&self) -> zbus::Result<()>;
+}
+
+/// Represents a shortcut to be bound through the portal.
+#[derive(Debug)]
+pub struct PortalShortcut {
+    /// The ID reported back when the shortcut is activated.
+    pub id: String,
+    /// The human-readable description shown by the desktop environment.
+    pub description: String,
+    /// The trigger suggested to the desktop environment, following the XDG shortcuts
+    /// specification, e.g., `CTRL+SHIFT+space`.
+    pub preferred_trigger: Option<String>,
+}
+
+/// Represents the failures of the portal that are not errors of the application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortalFailure {
+    /// The portal is not available, e.g., because the desktop environment does not implement it.
+    Unavailable,
+    /// The user dismissed the request, e.g., by closing the confirmation dialog.
+    Cancelled,
+}
+
+impl fmt::Display for PortalFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PortalFailure::Unavailable => write!(f, "The global shortcuts portal is not available"),
+            PortalFailure::Cancelled => write!(f, "The request was cancelled by the user"),
+        }
+    }
+}
+
+impl std::error::Error for PortalFailure {}
+
+/// Checks whether an error has been caused by the given failure of the portal.
+pub fn is_failure(error: &anyhow::Error, failure: PortalFailure) -> bool {
+    error.downcast_ref::<PortalFailure>() == Some(&failure)
+}
+
+/// Represents an open session of the portal.
+struct PortalSession {
+    connection: Connection,
+    handle: OwnedObjectPath,
+    /// The task forwarding the activations of the session's shortcuts.
+    listener: JoinHandle<()>,
+}
+
+impl PortalSession {
+    /// Closes the session, which releases all of its shortcuts.
+    async fn close(self) {
+        self.listener.abort();
+        let result = async {
+            SessionProxy::builder(&self.connection)
+                .path(self.handle.to_owned())?
+                .build()
+                .await?
+                .close()
+                .await
+        }
+        .await;
+        if let Err(e) = result {
+            log::warn!("Failed to close the global shortcuts portal session: {e:?}");
+        }
+    }
+}
+
+/// Binds the shortcuts in a new portal session, releasing those of the previous session.
+///
+/// `on_activated` is called with the ID of a shortcut whenever it is pressed.
+/// Returns the trigger descriptions of the shortcuts the user accepted, keyed by their IDs.
+/// Fails with `PortalFailure::Unavailable` if the portal is not available, and with
+/// `PortalFailure::Cancelled` if the user dismissed the request.
+pub async fn bind_shortcuts(
+    shortcuts: &[PortalShortcut],
+    on_activated: impl Fn(&str) + Send + 'static,
+) -> AppResult<HashMap<String, String>> {
+    let connection = Connection::session()
+        .await
+        .context("Failed to connect to the D-Bus session bus")
+        .context(PortalFailure::Unavailable)?;
+    bind_shortcuts_on(connection, shortcuts, on_activated).await
+}
+
+/// Binds the shortcuts in a new portal session on the given bus connection.
+async fn bind_shortcuts_on(
+    connection: Connection,
+    shortcuts: &[PortalShortcut],
+    on_activated: impl Fn(&str) + Send + 'static,
+) -> AppResult<HashMap<String, String>> {
+    let mut session = SESSION.lock().await;
+    if let Some(previous) = session.take() {
+        previous.close().await;
+    }
+
+    let portal = GlobalShortcutsProxy::new(&connection).await?;
+    let version = portal.version().await.context(PortalFailure::Unavailable)?;
+    log::info!(
+        "Binding {} global shortcuts via the portal (version {version})...",
+        shortcuts.len()
+    );
+    if shortcuts.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let session_token = new_token();
+    let results = request(&connection, |handle_token| {
+        portal.create_session(HashMap::from([
+            ("handle_token", Value::from(handle_token)),
+            ("session_handle_token", Value::from(session_token.as_str())),
+        ]))
+    })
+    .await
+    .context("Failed to create a global shortcuts portal session")?;
+    let handle = match results.get("session_handle").map(|value| &**value) {
+        Some(Value::Str(handle)) => OwnedObjectPath::try_from(handle.as_str())?,
+        Some(Value::ObjectPath(handle)) => OwnedObjectPath::from(handle.to_owned()),
+        _ => bail!("The global shortcuts portal did not return a session handle"),
+    };
+
+    let mut activations = portal.receive_activated().await?;
+    let session_handle = handle.to_owned();
+    let listener = async_runtime::spawn(async move {
+        while let Some(activation) = activations.next().await {
+            match activation.args() {
+                Ok(args) if args.session_handle == session_handle => {
+                    on_activated(&args.shortcut_id)
+                }
+                Ok(_) => {}
+                Err(e) => log::error!("{e:?}"),
+            }
+        }
+    });
+    *session = Some(PortalSession {
+        connection: connection.to_owned(),
+        handle: handle.to_owned(),
+        listener,
+    });
+
+    let shortcuts = shortcuts
+        .iter()
+        .map(|shortcut| {
+            let mut properties =
+                HashMap::from([("description", Value::from(shortcut.description.as_str()))]);
+            if let Some(preferred_trigger) = &shortcut.preferred_trigger {
+                properties.insert("preferred_trigger", Value::from(preferred_trigger.as_str()));
+            }
+            (shortcut.id.as_str(), properties)
+        })
+        .collect::<Vec<_>>();
+    let results = request(&connection, |handle_token| {
+        portal.bind_shortcuts(
+            &handle,
+            &shortcuts,
+            "",
+            HashMap::from([("handle_token", Value::from(handle_token))]),
+        )
+    })
+    .await
+    .context("Failed to bind the global shortcuts")?;
+
+    let bound = results
+        .get("shortcuts")
+        .map(|value| value.try_clone())
+        .transpose()?
+        .map(Vec::<(String, HashMap<String, OwnedValue>)>::try_from)
+        .transpose()?
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(id, properties)| {
+            let trigger = properties
+                .get("trigger_description")
+                .and_then(|value| value.downcast_ref::<String>().ok())
+                .unwrap_or_default();
+            (id, trigger)
+        })
+        .collect();
+    Ok(bound)
+}
+
+/// Makes a portal request and waits for its response.
+///
+/// The response signal is subscribed to before the request is made,
+/// so that it cannot be missed.
+async fn request<F, Fut>(connection: &Connection, call: F) -> AppResult<HashMap<String, OwnedValue>>
+where
+    F: FnOnce(String) -> Fut,
+    Fut: Future<Output = zbus::Result<OwnedObjectPath>>,
+{
+    let handle_token = new_token();
+    let sender = connection
+        .unique_name()
+        .context("The D-Bus connection has no unique name")?
+        .trim_start_matches(':')
+        .replace('.', "_");
+    let request = RequestProxy::builder(connection)
+        .path(format!(
+            "/org/freedesktop/portal/desktop/request/{sender}/{handle_token}"
+        ))?
+        .build()
+        .await?;
+    let mut responses = request.receive_response().await?;
+
+    call(handle_token.to_owned()).await?;
+
+    let response = responses
+        .next()
+        .await
+        .context("The portal closed the request without responding")?;
+    let args = response.args()?;
+    match args.response {
+        RESPONSE_SUCCESS => Ok(args.results),
+        RESPONSE_CANCELLED => Err(PortalFailure::Cancelled.into()),
+        code => Err(format_err!("The request failed with response code {code}")),
+    }
+}
+
+/// Generates a token that is valid as an element of a D-Bus object path.
+fn new_token() -> String {
+    let suffix = rand::rng()
+        .sample_iter(&rand::distr::Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect::<String>();
+    format!("capturist_{suffix}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use std::{env, fs};
+    use tokio::sync::mpsc;
+    use zbus::message::Header;
+    use zbus::names::BusName;
+
+    /// The configuration of the private bus the fake portal is served on.
+    const BUS_CONFIG: &str = r#"<busconfig>
+  <type>session</type>
+  <listen>unix:tmpdir=/tmp</listen>
+  <policy context="default">
+    <allow send_destination="*" eavesdrop="true"/>
+    <allow eavesdrop="true"/>
+    <allow own="*"/>
+  </policy>
+</busconfig>"#;
+
+    /// Serializes the tests, since the session of the portal is shared.
+    static TEST_LOCK: Mutex<()> = Mutex::const_new(());
+
+    /// Represents a private message bus, stopped once dropped.
+    struct PrivateBus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl PrivateBus {
+        fn start() -> Self {
+            let config = env::temp_dir().join(format!("{}.conf", new_token()));
+            fs::write(&config, BUS_CONFIG).unwrap();
+            let mut daemon = Command::new("dbus-daemon")
+                .arg(format!("--config-file={}", config.display()))
+                .args(["--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .expect("dbus-daemon is required to run the portal tests");
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take().unwrap())
+                .read_line(&mut address)
+                .unwrap();
+            let _ = fs::remove_file(config);
+            Self {
+                daemon,
+                address: address.trim().to_owned(),
+            }
+        }
+
+        async fn connect(&self) -> Connection {
+            zbus::connection::Builder::address(self.address.as_str())
+                .unwrap()
+                .build()
+                .await
+                .unwrap()
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    /// Stands in for the desktop portal, answering the binding requests with the given
+    /// response code and recording the IDs of the shortcuts it was asked to bind.
+    struct FakePortal {
+        bind_response: u32,
+        bound: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.portal.GlobalShortcuts")]
+    impl FakePortal {
+        async fn create_session(
+            &self,
+            options: HashMap<String, OwnedValue>,
+            #[zbus(header)] header: Header<'_>,
+            #[zbus(connection)] connection: &Connection,
+        ) -> zbus::fdo::Result<OwnedObjectPath> {
+            let session_handle = format!(
+                "/org/freedesktop/portal/desktop/session/test/{}",
+                get_string(&options, "session_handle_token")
+            );
+            let results = HashMap::from([("session_handle", Value::from(session_handle))]);
+            respond(connection, &header, &options, RESPONSE_SUCCESS, results).await
+        }
+
+        async fn bind_shortcuts(
+            &self,
+            _session_handle: OwnedObjectPath,
+            shortcuts: Vec<(String, HashMap<String, OwnedValue>)>,
+            _parent_window: String,
+            options: HashMap<String, OwnedValue>,
+            #[zbus(header)] header: Header<'_>,
+            #[zbus(connection)] connection: &Connection,
+        ) -> zbus::fdo::Result<OwnedObjectPath> {
+            let bound = shortcuts
+                .iter()
+                .map(|(id, properties)| {
+                    let trigger = get_string(properties, "preferred_trigger");
+                    let properties = HashMap::from([("trigger_description", Value::from(trigger))]);
+                    (id.to_owned(), properties)
+                })
+                .collect::<Vec<_>>();
+            self.bound
+                .lock()
+                .unwrap()
+                .extend(bound.iter().map(|(id, _)| id.to_owned()));
+            let results = HashMap::from([("shortcuts", Value::from(bound))]);
+            respond(connection, &header, &options, self.bind_response, results).await
+        }
+
+        #[zbus(property)]
+        fn version(&self) -> u32 {
+            1
+        }
+    }
+
+    /// Emits the response of a request on the object path the client expects it on.
+    async fn respond(
+        connection: &Connection,
+        header: &Header<'_>,
+        options: &HashMap<String, OwnedValue>,
+        response: u32,
+        results: HashMap<&str, Value<'_>>,
+    ) -> zbus::fdo::Result<OwnedObjectPath> {
+        let sender = header
+            .sender()
+            .map(|sender| sender.trim_start_matches(':').replace('.', "_"))
+            .unwrap_or_default();
+        let path = OwnedObjectPath::try_from(format!(
+            "/org/freedesktop/portal/desktop/request/{sender}/{}",
+            get_string(options, "handle_token")
+        ))
+        .map_err(zbus::Error::from)?;
+        connection
+            .emit_signal(
+                None::<BusName<'_>>,
+                &path,
+                "org.freedesktop.portal.Request",
+                "Response",
+                &(response, results),
+            )
+            .await?;
+        Ok(path)
+    }
+
+    /// Returns the string value of an option, or an empty string if it is missing.
+    fn get_string(options: &HashMap<String, OwnedValue>, key: &str) -> String {
+        options
+            .get(key)
+            .and_then(|value| value.downcast_ref::<String>().ok())
+            .unwrap_or_default()
+    }
+
+    /// Serves the fake portal on the bus and returns its connection and the bound shortcuts.
+    async fn serve_portal(
+        bus: &PrivateBus,
+        bind_response: u32,
+    ) -> (Connection, Arc<std::sync::Mutex<Vec<String>>>) {
+        let bound = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let portal = FakePortal {
+            bind_response,
+            bound: bound.to_owned(),
+        };
+        let connection = zbus::connection::Builder::address(bus.address.as_str())
+            .unwrap()
+            .name("org.freedesktop.portal.Desktop")
+            .unwrap()
+            .serve_at("/org/freedesktop/portal/desktop", portal)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        (connection, bound)
+    }
+
+    fn quick_add_shortcut() -> PortalShortcut {
+        PortalShortcut {
+            id: "quick-add".to_owned(),
+            description: "Open Quick-Add".to_owned(),
+            preferred_trigger: Some("CTRL+space".to_owned()),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn binds_the_shortcuts_and_forwards_their_activations() {
+        let _guard = TEST_LOCK.lock().await;
+        let bus = PrivateBus::start();
+        let (portal_connection, bound) = serve_portal(&bus, RESPONSE_SUCCESS).await;
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let triggers = bind_shortcuts_on(bus.connect().await, &[quick_add_shortcut()], move |id| {
+            let _ = sender.send(id.to_owned());
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            triggers,
+            HashMap::from([("quick-add".to_owned(), "CTRL+space".to_owned())])
+        );
+        assert_eq!(*bound.lock().unwrap(), ["quick-add"]);
+
+        let session_handle = SESSION.lock().await.as_ref().unwrap().handle.to_owned();
+        portal_connection
+            .emit_signal(
+                None::<BusName<'_>>,
+                "/org/freedesktop/portal/desktop",
+                "org.freedesktop.portal.GlobalShortcuts",
+                "Activated",
+                &(
+                    session_handle,
+                    "quick-add",
+                    0_u64,
+                    HashMap::<&str, Value<'_>>::new(),
+                ),
+            )
+            .await
+            .unwrap();
+        let activated = tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+            .await
+            .unwrap();
+        assert_eq!(activated.as_deref(), Some("quick-add"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reports_a_cancelled_binding() {
+        let _guard = TEST_LOCK.lock().await;
+        let bus = PrivateBus::start();
+        let (_portal_connection, bound) = serve_portal(&bus, RESPONSE_CANCELLED).await;
+
+        let error = bind_shortcuts_on(bus.connect().await, &[quick_add_shortcut()], |_| {})
+            .await
+            .unwrap_err();
+        assert!(is_failure(&error, PortalFailure::Cancelled));
+        assert!(!is_failure(&error, PortalFailure::Unavailable));
+        assert_eq!(*bound.lock().unwrap(), ["quick-add"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reports_a_missing_portal_as_unavailable() {
+        let _guard = TEST_LOCK.lock().await;
+        let bus = PrivateBus::start();
+
+        let error = bind_shortcuts_on(bus.connect().await, &[quick_add_shortcut()], |_| {})
+            .await
+            .unwrap_err();
+        assert!(is_failure(&error, PortalFailure::Unavailable));
+    }
+}
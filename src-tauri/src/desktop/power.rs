@@ -0,0 +1,54 @@
+//! This module detects when the system resumes from suspend, through the
+//! `org.freedesktop.login1.Manager` D-Bus interface of systemd-logind.
+//!
+//! Timers do not advance while the system is suspended, so the modules relying on
+//! them, e.g., the token refresh, are woken up with the `Resume` event instead.
+//!
+//! See: https://www.freedesktop.org/software/systemd/man/latest/org.freedesktop.login1.html
+
+use crate::ipc::events::CustomEvent;
+use crate::shared::error::AppResult;
+use anyhow::Context;
+use futures_util::StreamExt;
+use tauri::{AppHandle, Emitter};
+use zbus::Connection;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait LoginManager {
+    #[zbus(signal)]
+    fn prepare_for_sleep(&self, start: bool) -> zbus::Result<()>;
+}
+
+/// Sets up the detection of the system resuming from suspend.
+///
+/// If logind is not available, e.g., on systems without systemd, nothing is detected.
+pub fn set_up_resume_detection(app_handle: &AppHandle) {
+    log::info!("Setting up resume detection...");
+
+    let owned_app_handle = app_handle.to_owned();
+    tauri::async_runtime::spawn(async move {
+        let _ = listen_for_resume(&owned_app_handle)
+            .await
+            .inspect_err(|e| log::warn!("Resume detection is not available: {e:?}"));
+    });
+}
+
+/// Emits the `Resume` event whenever logind reports the end of a suspend.
+async fn listen_for_resume(app_handle: &AppHandle) -> AppResult<()> {
+    let connection = Connection::system()
+        .await
+        .context("Failed to connect to the D-Bus system bus")?;
+    let proxy = LoginManagerProxy::new(&connection).await?;
+    let mut signals = proxy.receive_prepare_for_sleep().await?;
+    while let Some(signal) = signals.next().await {
+        if !signal.args()?.start {
+            log::info!("The system has resumed from suspend.");
+            app_handle.emit(&CustomEvent::Resume.to_string(), ())?;
+        }
+    }
+    Ok(())
+}
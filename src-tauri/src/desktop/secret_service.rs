@@ -0,0 +1,114 @@
+//! This module watches and unlocks the keyring through the `org.freedesktop.Secret.Service`
+//! D-Bus interface, e.g., of GNOME Keyring or KWallet.
+//!
+//! While the keyring is locked, the secrets cannot be read, so the session is read again
+//! as soon as the keyring is unlocked, whether by the user in another application or
+//! through the prompt of `unlock_keyring`.
+//!
+//! See: https://specifications.freedesktop.org/secret-service-spec/latest/
+
+use crate::ipc::events::CustomEvent;
+use crate::shared::error::AppResult;
+use crate::shared::state::AppState;
+use crate::shared::storage;
+use crate::shared::storage::secret_store::{self, KeyringState};
+use anyhow::Context;
+use futures_util::StreamExt;
+use serde_json::json;
+use tauri::{AppHandle, Emitter, Manager};
+use zbus::zvariant::OwnedObjectPath;
+use zbus::Connection;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.Secret.Service",
+    default_service = "org.freedesktop.secrets",
+    default_path = "/org/freedesktop/secrets"
+)]
+trait SecretService {
+    #[zbus(signal)]
+    fn collection_changed(&self, collection: OwnedObjectPath) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.Secret.Collection",
+    default_service = "org.freedesktop.secrets"
+)]
+trait SecretCollection {
+    #[zbus(property)]
+    fn locked(&self) -> zbus::Result<bool>;
+}
+
+/// Sets up the detection of the keyring being unlocked.
+///
+/// If the Secret Service is not available, nothing is detected.
+pub fn set_up_unlock_detection(app_handle: &AppHandle) {
+    log::info!("Setting up keyring unlock detection...");
+
+    let owned_app_handle = app_handle.to_owned();
+    tauri::async_runtime::spawn(async move {
+        let _ = listen_for_unlock(&owned_app_handle)
+            .await
+            .inspect_err(|e| log::warn!("Keyring unlock detection is not available: {e:?}"));
+    });
+}
+
+/// Unlocks the default collection of the keyring, prompting the user for its password,
+/// and reads the session again.
+pub async fn unlock_keyring(app_handle: &AppHandle) -> AppResult<()> {
+    tauri::async_runtime::spawn_blocking(unlock_default_collection).await??;
+    log::info!("The keyring has been unlocked.");
+    reload_session(app_handle)
+}
+
+/// Reads the session again whenever a collection is unlocked while the keyring is locked.
+async fn listen_for_unlock(app_handle: &AppHandle) -> AppResult<()> {
+    let connection = Connection::session()
+        .await
+        .context("Failed to connect to the D-Bus session bus")?;
+    let proxy = SecretServiceProxy::new(&connection).await?;
+    let mut signals = proxy.receive_collection_changed().await?;
+    while let Some(signal) = signals.next().await {
+        if secret_store::get_keyring_state(app_handle) != KeyringState::Locked {
+            continue;
+        }
+        let collection = SecretCollectionProxy::builder(&connection)
+            .path(signal.args()?.collection.to_owned())?
+            .build()
+            .await?;
+        if !collection.locked().await? {
+            log::info!("A keyring collection has been unlocked.");
+            let _ = reload_session(app_handle).inspect_err(|e| log::error!("{e:?}"));
+        }
+    }
+    Ok(())
+}
+
+/// Reads the session from the keyring again, which marks the keyring as available and
+/// retries the operations waiting for it, and updates the authentication state.
+fn reload_session(app_handle: &AppHandle) -> AppResult<()> {
+    let authenticated = storage::credentials::find(app_handle)?.is_some();
+    if authenticated != *app_handle.state::<AppState>().authenticated.lock().unwrap() {
+        app_handle.emit(
+            &CustomEvent::Authentication.to_string(),
+            json!(authenticated),
+        )?;
+    }
+    Ok(())
+}
+
+/// Unlocks the default collection of the Secret Service, blocking until the user has
+/// answered the prompt.
+#[cfg(target_os = "linux")]
+fn unlock_default_collection() -> AppResult<()> {
+    use dbus_secret_service::{EncryptionType, SecretService};
+
+    let service = SecretService::connect(EncryptionType::Plain)?;
+    service.get_default_collection()?.unlock()?;
+    Ok(())
+}
+
+/// Unlocking the keyring is only supported through the Secret Service.
+#[cfg(not(target_os = "linux"))]
+fn unlock_default_collection() -> AppResult<()> {
+    anyhow::bail!("Unlocking the keyring is not supported on this platform")
+}
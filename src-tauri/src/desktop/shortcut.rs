@@ -1,49 +1,528 @@
+use crate::desktop::notification::{self, NotificationAction, NotificationContext};
+use crate::desktop::portal::{self, PortalFailure, PortalShortcut};
+use crate::external::todoist::task::{self, NewTask, TaskSubmission};
+use crate::ipc::events::CustomEvent;
+use crate::shared::environment;
 use crate::shared::error::AppResult;
+use crate::shared::state::AppState;
+use crate::shared::storage::general;
+use crate::shared::storage::key::StorageKey;
+use crate::shared::storage::settings::{self, Settings};
 use crate::window;
-use tauri::AppHandle;
+use anyhow::{bail, ensure, format_err, Context};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter, Listener, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
 use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
 use tauri_plugin_log::log;
+use tauri_plugin_opener::OpenerExt;
+use tokio::sync::Mutex;
 
-/// Sets up the global shortcut for opening the Quick-Add dialog.
+/// The URL opened by the `OpenTodoist` action.
+const TODOIST_APP_URL: &str = "https://app.todoist.com/app";
+
+/// Held while the bindings are being applied, so that unregistering all of the shortcuts
+/// cannot interleave with the registrations of another application of the bindings.
+static APPLYING: Mutex<()> = Mutex::const_new(());
+
+/// Represents an action that can be triggered by a global shortcut.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ShortcutAction {
+    /// Open the Quick-Add dialog.
+    QuickAdd,
+    /// Add the text in the clipboard as a new task without opening any window.
+    CaptureClipboard,
+    /// Open Todoist in the browser.
+    OpenTodoist,
+}
+
+impl fmt::Display for ShortcutAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShortcutAction::QuickAdd => write!(f, "quick-add"),
+            ShortcutAction::CaptureClipboard => write!(f, "capture-clipboard"),
+            ShortcutAction::OpenTodoist => write!(f, "open-todoist"),
+        }
+    }
+}
+
+impl TryFrom<&str> for ShortcutAction {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> AppResult<Self> {
+        match value {
+            "quick-add" => Ok(Self::QuickAdd),
+            "capture-clipboard" => Ok(Self::CaptureClipboard),
+            "open-todoist" => Ok(Self::OpenTodoist),
+            _ => Err(format_err!("Unknown shortcut action: {}", value)),
+        }
+    }
+}
+
+impl ShortcutAction {
+    /// Returns the human-readable description of the action, e.g., for the desktop environment.
+    fn description(&self) -> &'static str {
+        match self {
+            ShortcutAction::QuickAdd => "Open Quick-Add",
+            ShortcutAction::CaptureClipboard => "Add clipboard text as a task",
+            ShortcutAction::OpenTodoist => "Open Todoist",
+        }
+    }
+}
+
+/// Represents an accelerator bound to an action, e.g., `Ctrl+Shift+Space` to `quick-add`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShortcutBinding {
+    pub action: ShortcutAction,
+    pub accelerator: String,
+}
+
+/// Represents the registration result of a binding, as reported to the frontend.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShortcutStatus {
+    pub action: ShortcutAction,
+    /// The accelerator, or on Wayland, the trigger assigned by the desktop environment.
+    pub accelerator: String,
+    /// Whether the accelerator is currently registered.
+    pub registered: bool,
+    /// Why the registration failed, e.g., because another application already uses the accelerator.
+    pub error: Option<String>,
+}
+
+/// Sets up the global shortcuts bound by the user.
 ///
-/// The shortcut is `Alt + Space` on macOS and `Ctrl + Space` on other operating systems.
-/// When the shortcut is pressed, it attempts to open the Quick-Add dialog.
+/// By default, only the Quick-Add dialog is bound, to `Alt + Space` on macOS and
+/// `Ctrl + Space` on other operating systems.
+/// The shortcuts are only registered while the `global_shortcut` setting is enabled,
+/// and are applied again whenever the setting changes.
 ///
-/// Wayland is currently unsupported.
+/// On Wayland, the shortcuts are bound through the XDG desktop portal. Each application
+/// opens a new portal session, so the desktop environment may ask the user to confirm
+/// them again; if the user dismisses it, the shortcuts stay unbound. Only if the portal
+/// is not available, the X11 registration is used instead, which only works while an
+/// XWayland window is focused.
 ///
 /// TODO:
-///  05/11/2025 Enable global shortcut and make it configurable after the following issue got resolved.
+///  05/11/2025 Enable global shortcut by default after the following issue got resolved.
 ///
 /// See: https://github.com/tauri-apps/global-hotkey/issues/28
-#[allow(dead_code)]
 pub fn set_up_global_shortcut(app_handle: &AppHandle) -> AppResult<()> {
-    log::info!("Setting up global shortcut...");
+    log::info!("Setting up global shortcuts...");
+
+    let toggle = ShortcutToggle::new(settings::get(app_handle)?.global_shortcut);
+    spawn_apply_bindings(app_handle);
+
+    let owned_app_handle = app_handle.to_owned();
+    app_handle.listen(CustomEvent::Settings.to_string(), move |event| {
+        let _ = serde_json::from_str::<Settings>(event.payload())
+            .context("Failed to deserialize settings event")
+            .map(|settings| {
+                if toggle.update(settings.global_shortcut) {
+                    spawn_apply_bindings(&owned_app_handle);
+                }
+            })
+            .inspect_err(|e| log::error!("{e:?}"));
+    });
+
+    Ok(())
+}
+
+/// Returns the accelerator bound to the Quick-Add dialog, if any.
+pub fn get_global_shortcut_accelerator(app_handle: &AppHandle) -> AppResult<Option<String>> {
+    let accelerator = get_bindings(app_handle)?
+        .into_iter()
+        .find(|binding| binding.action == ShortcutAction::QuickAdd)
+        .map(|binding| binding.accelerator);
+    Ok(accelerator)
+}
+
+/// Retrieves the bindings of the global shortcuts, falling back to the default one.
+pub fn get_bindings(app_handle: &AppHandle) -> AppResult<Vec<ShortcutBinding>> {
+    let bindings = general::find(StorageKey::GlobalShortcuts, app_handle)?.unwrap_or_else(|| {
+        vec![ShortcutBinding {
+            action: ShortcutAction::QuickAdd,
+            accelerator: get_default_shortcut().to_string(),
+        }]
+    });
+    Ok(bindings)
+}
+
+/// Returns the registration results of the current bindings.
+pub fn get_statuses(app_handle: &AppHandle) -> Vec<ShortcutStatus> {
+    app_handle
+        .state::<AppState>()
+        .shortcut_statuses
+        .lock()
+        .unwrap()
+        .to_owned()
+}
+
+/// Validates and stores the bindings of the global shortcuts, then re-registers them.
+///
+/// Fails without changing anything if an accelerator is invalid, or if an action or
+/// an accelerator appears more than once. Accelerators that cannot be registered,
+/// e.g., because they are taken by another application, are reported in the returned statuses.
+pub async fn update_bindings(
+    bindings: Vec<ShortcutBinding>,
+    app_handle: &AppHandle,
+) -> AppResult<Vec<ShortcutStatus>> {
+    log::info!("Updating global shortcuts: {bindings:?}");
+
+    let mut actions = HashMap::new();
+    let mut shortcuts = HashMap::new();
+    for binding in &bindings {
+        let shortcut = parse_accelerator(&binding.accelerator)?;
+        ensure!(
+            actions.insert(binding.action, ()).is_none(),
+            "The {} action is bound more than once",
+            binding.action
+        );
+        if let Some(action) = shortcuts.insert(shortcut.id(), binding.action) {
+            bail!(
+                "{} is already bound to the {action} action",
+                binding.accelerator
+            );
+        }
+    }
+
+    general::set(StorageKey::GlobalShortcuts, &bindings, app_handle)?;
+    apply_bindings(app_handle).await
+}
+
+/// Parses an accelerator, e.g., `Ctrl+Shift+Space`.
+fn parse_accelerator(accelerator: &str) -> AppResult<Shortcut> {
+    accelerator
+        .parse::<Shortcut>()
+        .map_err(|e| format_err!("Invalid shortcut {accelerator:?}: {e}"))
+}
+
+/// Converts an accelerator to a trigger of the XDG shortcuts specification,
+/// e.g., `Ctrl+Shift+Space` to `CTRL+SHIFT+space`.
+pub fn to_xdg_trigger(accelerator: &str) -> AppResult<String> {
+    let shortcut = parse_accelerator(accelerator)?;
+    let mut trigger = [
+        (Modifiers::CONTROL, "CTRL"),
+        (Modifiers::ALT, "ALT"),
+        (Modifiers::SHIFT, "SHIFT"),
+        (Modifiers::SUPER, "LOGO"),
+    ]
+    .into_iter()
+    .filter(|(modifier, _)| shortcut.mods.contains(*modifier))
+    .map(|(_, name)| name.to_owned())
+    .collect::<Vec<_>>();
+    let key = shortcut.key.to_string();
+    trigger.push(match key.as_str() {
+        "Space" => "space".to_owned(),
+        "Enter" => "Return".to_owned(),
+        _ => key
+            .strip_prefix("Key")
+            .or_else(|| key.strip_prefix("Digit"))
+            .map(str::to_lowercase)
+            .unwrap_or(key.to_owned()),
+    });
+    Ok(trigger.join("+"))
+}
+
+/// Tracks the `global_shortcut` setting, so that the bindings are only applied again
+/// when it changes rather than on every change of the settings.
+struct ShortcutToggle(AtomicBool);
+
+impl ShortcutToggle {
+    fn new(enabled: bool) -> Self {
+        Self(AtomicBool::new(enabled))
+    }
 
-    let shortcut = get_global_shortcut();
-    if app_handle.global_shortcut().is_registered(shortcut) {
-        log::info!("Global shortcut is already registered. Skipping the setup...");
-        return Ok(());
+    /// Records the current value of the setting and returns whether it has changed.
+    fn update(&self, enabled: bool) -> bool {
+        self.0.swap(enabled, Ordering::SeqCst) != enabled
     }
+}
+
+/// Applies the bindings in the background, e.g., when the settings change.
+fn spawn_apply_bindings(app_handle: &AppHandle) {
+    let owned_app_handle = app_handle.to_owned();
+    tauri::async_runtime::spawn(async move {
+        let _ = apply_bindings(&owned_app_handle)
+            .await
+            .inspect_err(|e| log::error!("{e:?}"));
+    });
+}
+
+/// Runs an application of the bindings once the previous one has finished.
+async fn serialize_application<T>(application: impl Future<Output = T>) -> T {
+    let _applying = APPLYING.lock().await;
+    application.await
+}
+
+/// Unregisters all the global shortcuts and, if the `global_shortcut` setting is enabled,
+/// registers the stored bindings again.
+///
+/// The applications are serialized, and each one reads the setting once it has started,
+/// so the last one always reflects the latest settings.
+/// The registration results are kept in the state and broadcast with the `GlobalShortcuts` event.
+async fn apply_bindings(app_handle: &AppHandle) -> AppResult<Vec<ShortcutStatus>> {
+    serialize_application(async {
+        let enable = settings::get(app_handle)?.global_shortcut;
+        app_handle.global_shortcut().unregister_all()?;
+
+        let bindings = get_bindings(app_handle)?;
+        let statuses = if environment::is_running_on_wayland() {
+            match bind_via_portal(enable, &bindings, app_handle).await {
+                Ok(statuses) => statuses,
+                Err(e) if portal::is_failure(&e, PortalFailure::Unavailable) => {
+                    log::warn!("Falling back to X11 global shortcuts: {e:?}");
+                    register_bindings(enable, bindings, app_handle)
+                }
+                Err(e) => {
+                    log::warn!("Failed to bind the global shortcuts via the portal: {e:?}");
+                    if portal::is_failure(&e, PortalFailure::Cancelled) {
+                        spawn_cancellation_warning(app_handle);
+                    }
+                    get_unbound_statuses(bindings, &e)
+                }
+            }
+        } else {
+            register_bindings(enable, bindings, app_handle)
+        };
+
+        *app_handle
+            .state::<AppState>()
+            .shortcut_statuses
+            .lock()
+            .unwrap() = statuses.to_owned();
+        app_handle.emit(&CustomEvent::GlobalShortcuts.to_string(), &statuses)?;
+
+        Ok(statuses)
+    })
+    .await
+}
+
+/// Binds the shortcuts through the XDG desktop portal, or releases them if not enabled.
+async fn bind_via_portal(
+    enable: bool,
+    bindings: &[ShortcutBinding],
+    app_handle: &AppHandle,
+) -> AppResult<Vec<ShortcutStatus>> {
+    let shortcuts = bindings
+        .iter()
+        .filter(|_| enable)
+        .map(|binding| PortalShortcut {
+            id: binding.action.to_string(),
+            description: binding.action.description().to_owned(),
+            preferred_trigger: to_xdg_trigger(&binding.accelerator).ok(),
+        })
+        .collect::<Vec<_>>();
+    let owned_app_handle = app_handle.to_owned();
+    let triggers = portal::bind_shortcuts(&shortcuts, move |id| {
+        log::info!("Global shortcut triggered via the portal: {id}");
+        let _ = ShortcutAction::try_from(id)
+            .and_then(|action| trigger_action(action, &owned_app_handle))
+            .inspect_err(|e| log::error!("{e:?}"));
+    })
+    .await?;
+
+    let statuses = bindings
+        .iter()
+        .map(|binding| {
+            let trigger = triggers.get(&binding.action.to_string());
+            ShortcutStatus {
+                action: binding.action,
+                accelerator: trigger
+                    .filter(|trigger| !trigger.is_empty())
+                    .unwrap_or(&binding.accelerator)
+                    .to_owned(),
+                registered: trigger.is_some(),
+                error: (enable && trigger.is_none())
+                    .then(|| "The shortcut was not accepted by the desktop environment".to_owned()),
+            }
+        })
+        .collect();
+    Ok(statuses)
+}
+
+/// Reports the bindings as not registered because of the given error.
+fn get_unbound_statuses(
+    bindings: Vec<ShortcutBinding>,
+    error: &anyhow::Error,
+) -> Vec<ShortcutStatus> {
+    bindings
+        .into_iter()
+        .map(|binding| ShortcutStatus {
+            action: binding.action,
+            accelerator: binding.accelerator,
+            registered: false,
+            error: Some(format!("{error:#}")),
+        })
+        .collect()
+}
+
+/// Warns the user that the global shortcuts are not bound, since they dismissed
+/// the confirmation of the desktop environment.
+fn spawn_cancellation_warning(app_handle: &AppHandle) {
+    let owned_app_handle = app_handle.to_owned();
+    tauri::async_runtime::spawn(async move {
+        let _ = notification::send(
+            "Global shortcuts not bound",
+            "The shortcuts were not confirmed, so they are disabled. \
+                Save them again in the settings to be asked again.",
+            &[],
+            NotificationContext::default(),
+            &owned_app_handle,
+        )
+        .await
+        .inspect_err(|e| log::error!("{e:?}"));
+    });
+}
+
+/// Registers the bindings through the global shortcut plugin, if enabled.
+fn register_bindings(
+    enable: bool,
+    bindings: Vec<ShortcutBinding>,
+    app_handle: &AppHandle,
+) -> Vec<ShortcutStatus> {
+    bindings
+        .into_iter()
+        .map(|binding| {
+            let result = if enable {
+                register_binding(&binding, app_handle).map(|_| true)
+            } else {
+                Ok(false)
+            };
+            ShortcutStatus {
+                action: binding.action,
+                accelerator: binding.accelerator,
+                registered: result.as_ref().is_ok_and(|registered| *registered),
+                error: result
+                    .inspect_err(|e| log::warn!("{e:?}"))
+                    .err()
+                    .map(|e| format!("{e:#}")),
+            }
+        })
+        .collect()
+}
+
+/// Registers a single binding, so that its action is triggered when the accelerator is pressed.
+fn register_binding(binding: &ShortcutBinding, app_handle: &AppHandle) -> AppResult<()> {
+    log::info!(
+        "Registering the global shortcut {} for {}...",
+        binding.accelerator,
+        binding.action
+    );
+
+    let shortcut = parse_accelerator(&binding.accelerator)?;
+    let action = binding.action;
     app_handle
         .global_shortcut()
-        .on_shortcut(shortcut, move |app_handle, _shortcut, event| {
+        .on_shortcut(shortcut, move |app_handle, shortcut, event| {
             if event.state == ShortcutState::Pressed {
-                log::info!("Global shortcut triggered: {}", shortcut);
-                let _ = window::init_quick_add_dialog(app_handle, false)
-                    .inspect_err(|e| log::error!("{e:?}"));
+                log::info!("Global shortcut triggered: {shortcut} ({action})");
+                let _ = trigger_action(action, app_handle).inspect_err(|e| log::error!("{e:?}"));
             }
+        })
+        .with_context(|| {
+            format!(
+                "{} could not be registered; it may be in use by another application",
+                binding.accelerator
+            )
         })?;
 
     Ok(())
 }
 
-/// Returns the accelerator string for the global shortcut.
-pub fn get_global_shortcut_accelerator() -> String {
-    get_global_shortcut().to_string()
+/// Performs the action bound to a global shortcut.
+fn trigger_action(action: ShortcutAction, app_handle: &AppHandle) -> AppResult<()> {
+    match action {
+        ShortcutAction::QuickAdd => window::init_quick_add_dialog(app_handle, false),
+        ShortcutAction::CaptureClipboard => capture_clipboard(app_handle),
+        ShortcutAction::OpenTodoist => {
+            app_handle
+                .opener()
+                .open_url(TODOIST_APP_URL, None::<&str>)?;
+            Ok(())
+        }
+    }
+}
+
+/// Adds the text in the clipboard as a new task and notifies the user about the result.
+fn capture_clipboard(app_handle: &AppHandle) -> AppResult<()> {
+    let content = app_handle.clipboard().read_text()?.trim().to_owned();
+    ensure!(
+        !content.is_empty(),
+        "The clipboard does not contain any text"
+    );
+
+    let owned_app_handle = app_handle.to_owned();
+    tauri::async_runtime::spawn(async move {
+        let new_task = NewTask {
+            content,
+            description: None,
+            project_id: None,
+            labels: Vec::new(),
+            priority: None,
+            due: None,
+        };
+        let result = task::submit_task(new_task.to_owned(), &owned_app_handle).await;
+        let _ = notify_capture_result(new_task, result, &owned_app_handle)
+            .await
+            .inspect_err(|e| log::error!("{e:?}"));
+    });
+
+    Ok(())
 }
 
-/// Returns the platform-specific global shortcut.
-fn get_global_shortcut() -> Shortcut {
+/// Sends a notification about the result of capturing the clipboard, if the user wants one.
+async fn notify_capture_result(
+    new_task: NewTask,
+    result: AppResult<TaskSubmission>,
+    app_handle: &AppHandle,
+) -> AppResult<()> {
+    if let Err(e) = &result {
+        log::error!("{e:?}");
+    }
+
+    let settings = settings::get(app_handle)?;
+    let (title, body, actions, context) = match result {
+        Ok(TaskSubmission::Created { task }) if settings.notify_on_success => (
+            "Task added",
+            task.content.to_owned(),
+            if settings.undo {
+                vec![NotificationAction::OpenInTodoist, NotificationAction::Undo]
+            } else {
+                vec![NotificationAction::OpenInTodoist]
+            },
+            NotificationContext {
+                task_id: Some(task.id),
+                task_url: Some(task.url),
+                new_task: Some(new_task),
+            },
+        ),
+        Ok(TaskSubmission::Queued { .. }) if settings.notify_on_success => (
+            "Task queued",
+            "It will be added as soon as Todoist is reachable again.".to_owned(),
+            Vec::new(),
+            NotificationContext::default(),
+        ),
+        Err(e) if settings.notify_on_failure => (
+            "Failed to add task",
+            e.to_string(),
+            vec![NotificationAction::Edit],
+            NotificationContext {
+                new_task: Some(new_task),
+                ..NotificationContext::default()
+            },
+        ),
+        _ => return Ok(()),
+    };
+    notification::send(title, &body, &actions, context, app_handle).await
+}
+
+/// Returns the platform-specific default shortcut for the Quick-Add dialog.
+fn get_default_shortcut() -> Shortcut {
     #[cfg(target_os = "macos")]
     {
         Shortcut::new(Some(Modifiers::ALT), Code::Space)
@@ -53,3 +532,43 @@ fn get_global_shortcut() -> Shortcut {
         Shortcut::new(Some(Modifiers::CONTROL), Code::Space)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn reports_only_the_changes_of_the_setting() {
+        let toggle = ShortcutToggle::new(false);
+        let changes = [false, true, true, true, false, false]
+            .into_iter()
+            .map(|enabled| toggle.update(enabled))
+            .collect::<Vec<_>>();
+        assert_eq!(changes, [false, true, false, false, true, false]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn serializes_concurrent_applications() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let applications = (0..4)
+            .map(|_| {
+                let running = running.to_owned();
+                let max_running = max_running.to_owned();
+                tokio::spawn(serialize_application(async move {
+                    let count = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(count, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                }))
+            })
+            .collect::<Vec<_>>();
+        for application in applications {
+            application.await.unwrap();
+        }
+        assert_eq!(max_running.load(Ordering::SeqCst), 1);
+    }
+}
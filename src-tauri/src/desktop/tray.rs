@@ -1,20 +1,22 @@
 //! This module manages the system tray icon and its associated menu,
 //! allowing users to interact with the application directly from the system tray.
 
+use crate::desktop::notification::{self, NotificationContext};
 use crate::desktop::window;
-use crate::external::todoist::auth;
+use crate::external::todoist::{auth, task};
 use crate::ipc::events::CustomEvent;
 use crate::shared::environment;
 use crate::shared::error::AppResult;
 use crate::shared::metadata::APP_TITLE;
 use crate::shared::state::AppState;
+use crate::shared::storage::history::{self, CaptureStatus};
+use crate::shared::storage::settings::{self, Settings};
 use anyhow::{format_err, Context};
-use serde_json::json;
 use std::fmt;
 use std::ops::Not;
-use tauri::menu::{CheckMenuItem, Menu, MenuBuilder, MenuEvent, MenuItem};
+use tauri::menu::{CheckMenuItem, Menu, MenuBuilder, MenuEvent, MenuItem, Submenu};
 use tauri::tray::TrayIconBuilder;
-use tauri::{AppHandle, Emitter, Event, Listener, Manager, Wry};
+use tauri::{AppHandle, Event, Listener, Manager, Wry};
 use tauri_plugin_autostart::ManagerExt;
 use tauri_plugin_log::log;
 
@@ -25,12 +27,18 @@ use tauri_plugin_log::log;
 enum MenuId {
     /// Opens the Quick-Add dialog.
     QuickAdd,
+    /// Deletes the most recently captured task.
+    UndoLastTask,
+    /// Lists the most recently captured tasks.
+    Recent,
     /// Opens the settings window.
     Settings,
     /// Toggles the application's autostart setting.
     AutoStart,
     /// Logs the user out of the application.
     LogOut,
+    /// Logs the user out of the application and clears all the cached data.
+    LogOutEverywhere,
     /// Quits the application.
     Quit,
 }
@@ -39,9 +47,12 @@ impl fmt::Display for MenuId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             MenuId::QuickAdd => write!(f, "quick-add"),
+            MenuId::UndoLastTask => write!(f, "undo-last-task"),
+            MenuId::Recent => write!(f, "recent"),
             MenuId::Settings => write!(f, "settings"),
             MenuId::AutoStart => write!(f, "autostart"),
             MenuId::LogOut => write!(f, "log-out"),
+            MenuId::LogOutEverywhere => write!(f, "log-out-everywhere"),
             MenuId::Quit => write!(f, "quit"),
         }
     }
@@ -53,9 +64,12 @@ impl TryFrom<&str> for MenuId {
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
             "quick-add" => Ok(Self::QuickAdd),
+            "undo-last-task" => Ok(Self::UndoLastTask),
+            "recent" => Ok(Self::Recent),
             "settings" => Ok(Self::Settings),
             "autostart" => Ok(Self::AutoStart),
             "log-out" => Ok(Self::LogOut),
+            "log-out-everywhere" => Ok(Self::LogOutEverywhere),
             "quit" => Ok(Self::Quit),
             _ => Err("Unknown menu ID."),
         }
@@ -71,6 +85,15 @@ const TRAY_MENU_ID: &str = "capturist-tray-menu";
 /// The title/tooltip of the system tray icon.
 const TRAY_TITLE: &str = APP_TITLE;
 
+/// The prefix of the IDs of the items in the "Recent" submenu, followed by the history entry ID.
+const RECENT_ITEM_ID_PREFIX: &str = "recent-task:";
+
+/// The number of captured tasks listed in the "Recent" submenu.
+const RECENT_ITEMS_LIMIT: usize = 10;
+
+/// The maximum number of characters of a task's content shown in the "Recent" submenu.
+const RECENT_ITEM_MAX_CHARS: usize = 40;
+
 /// Sets up the system tray icon and its associated menu.
 pub fn set_up_tray_menu(app_handle: &AppHandle) -> AppResult<()> {
     log::info!("Setting up the tray menu...");
@@ -89,8 +112,23 @@ pub fn set_up_tray_menu(app_handle: &AppHandle) -> AppResult<()> {
         .build(app_handle)?;
 
     let owned_tray_menu = tray_menu.to_owned();
+    let owned_app_handle = app_handle.to_owned();
     app_handle.listen(CustomEvent::Authentication.to_string(), move |event| {
-        let _ = on_authentication_state_change(&owned_tray_menu, event)
+        let _ = on_authentication_state_change(&owned_app_handle, &owned_tray_menu, event)
+            .inspect_err(|e| log::error!("{e:?}"));
+    });
+
+    let owned_tray_menu = tray_menu.to_owned();
+    let owned_app_handle = app_handle.to_owned();
+    app_handle.listen(CustomEvent::Settings.to_string(), move |event| {
+        let _ = on_settings_change(&owned_app_handle, &owned_tray_menu, event)
+            .inspect_err(|e| log::error!("{e:?}"));
+    });
+
+    let owned_tray_menu = tray_menu.to_owned();
+    let owned_app_handle = app_handle.to_owned();
+    app_handle.listen(CustomEvent::History.to_string(), move |_| {
+        let _ = on_history_change(&owned_app_handle, &owned_tray_menu)
             .inspect_err(|e| log::error!("{e:?}"));
     });
 
@@ -122,6 +160,18 @@ fn get_tray_menu(app_handle: &AppHandle) -> AppResult<Menu<Wry>> {
         None::<String>,
     )?);
 
+    menu_builder = menu_builder.item(&MenuItem::with_id(
+        app_handle,
+        MenuId::UndoLastTask.to_string(),
+        "Undo last task",
+        user_authenticated && settings::get(app_handle)?.undo,
+        None::<String>,
+    )?);
+
+    let recent_submenu = Submenu::with_id(app_handle, MenuId::Recent.to_string(), "Recent", true)?;
+    rebuild_recent_submenu(app_handle, &recent_submenu)?;
+    menu_builder = menu_builder.item(&recent_submenu);
+
     if environment::is_running_as_snap().not() && environment::is_running_as_flatpak().not() {
         menu_builder = menu_builder.separator().item(&CheckMenuItem::with_id(
             app_handle,
@@ -134,6 +184,7 @@ fn get_tray_menu(app_handle: &AppHandle) -> AppResult<Menu<Wry>> {
     }
 
     menu_builder
+        .text(MenuId::Settings.to_string(), "Settings")
         .separator()
         .item(&MenuItem::with_id(
             app_handle,
@@ -142,6 +193,13 @@ fn get_tray_menu(app_handle: &AppHandle) -> AppResult<Menu<Wry>> {
             user_authenticated,
             None::<String>,
         )?)
+        .item(&MenuItem::with_id(
+            app_handle,
+            MenuId::LogOutEverywhere.to_string(),
+            "Log out everywhere and clear data",
+            user_authenticated,
+            None::<String>,
+        )?)
         .text(MenuId::Quit.to_string(), "Quit Capturist")
         .build()
         .context("Failed to build the tray menu.")
@@ -149,20 +207,29 @@ fn get_tray_menu(app_handle: &AppHandle) -> AppResult<Menu<Wry>> {
 
 /// Handles events triggered by interactions with the system tray menu.
 fn handle_menu_event(app_handle: &AppHandle, event: MenuEvent, menu: &Menu<Wry>) {
+    if let Some(entry_id) = event.id().as_ref().strip_prefix(RECENT_ITEM_ID_PREFIX) {
+        let _ = task::open_in_todoist(entry_id, app_handle).inspect_err(|e| log::error!("{e:?}"));
+        return;
+    }
+
     match event.id().as_ref().try_into() {
         Ok(menu_id) => match menu_id {
             MenuId::QuickAdd => {
                 let _ = window::init_quick_add_dialog(app_handle, false)
                     .inspect_err(|e| log::error!("{e:?}"));
             }
+            MenuId::UndoLastTask => notification::spawn_undo_and_notify(None, app_handle),
+            MenuId::Recent => {}
             MenuId::AutoStart => {
                 let _ = toggle_autostart(app_handle, menu).inspect_err(|e| log::error!("{e:?}"));
             }
-            MenuId::LogOut => {
-                let _ = auth::log_out(app_handle).inspect_err(|e| log::error!("{e:?}"));
-            }
+            MenuId::LogOut => log_out(false, app_handle),
+            MenuId::LogOutEverywhere => log_out(true, app_handle),
             MenuId::Quit => app_handle.exit(0),
-            MenuId::Settings => todo!("Settings menu item clicked (not implemented)"),
+            MenuId::Settings => {
+                let _ =
+                    window::init_settings_window(app_handle).inspect_err(|e| log::error!("{e:?}"));
+            }
         },
         Err(_) => {
             log::warn!("Unknown menu ID: {}", event.id().as_ref());
@@ -177,26 +244,141 @@ fn toggle_autostart(app_handle: &AppHandle, menu: &Menu<Wry>) -> AppResult<()> {
         .and_then(|menu_item| menu_item.as_check_menuitem().cloned())
         .context("Failed to retrieve the autostart menu item.")?
         .is_checked()?;
-    app_handle.emit(
-        &CustomEvent::Autostart.to_string(),
-        json!(is_autostart_menu_item_checked),
-    )?;
+    settings::modify(app_handle, |settings| {
+        settings.autostart = is_autostart_menu_item_checked
+    })?;
 
     Ok(())
 }
 
+/// Handles the settings change event, keeping the autostart and undo menu items in sync.
+fn on_settings_change(
+    app_handle: &AppHandle,
+    owned_tray_menu: &Menu<Wry>,
+    event: Event,
+) -> AppResult<()> {
+    let settings = serde_json::from_str::<Settings>(event.payload())?;
+    let user_authenticated = app_handle
+        .state::<AppState>()
+        .authenticated
+        .lock()
+        .unwrap()
+        .to_owned();
+    get_menu_item(owned_tray_menu, MenuId::UndoLastTask)?
+        .set_enabled(user_authenticated && settings.undo)?;
+    if let Some(autostart_menu_item) = owned_tray_menu
+        .get(&MenuId::AutoStart.to_string())
+        .and_then(|menu_item| menu_item.as_check_menuitem().cloned())
+    {
+        autostart_menu_item.set_checked(settings.autostart)?;
+    }
+
+    Ok(())
+}
+
+/// Handles the history change event, e.g., a task was captured, rebuilding the "Recent" submenu.
+fn on_history_change(app_handle: &AppHandle, owned_tray_menu: &Menu<Wry>) -> AppResult<()> {
+    let recent_submenu = owned_tray_menu
+        .get(&MenuId::Recent.to_string())
+        .and_then(|menu_item| menu_item.as_submenu().cloned())
+        .context("Failed to retrieve the recent submenu.")?;
+    rebuild_recent_submenu(app_handle, &recent_submenu)
+}
+
+/// Replaces the items of the "Recent" submenu with the most recently captured tasks.
+///
+/// Only the tasks that exist in Todoist can be clicked, which opens them there.
+fn rebuild_recent_submenu(app_handle: &AppHandle, recent_submenu: &Submenu<Wry>) -> AppResult<()> {
+    for item in recent_submenu.items()? {
+        recent_submenu.remove(&item)?;
+    }
+
+    let entries = history::list(app_handle)?;
+    if entries.is_empty() {
+        recent_submenu.append(&MenuItem::new(
+            app_handle,
+            "No captured tasks",
+            false,
+            None::<String>,
+        )?)?;
+    }
+    for entry in entries.into_iter().take(RECENT_ITEMS_LIMIT) {
+        let mut content = entry
+            .task
+            .content
+            .chars()
+            .take(RECENT_ITEM_MAX_CHARS)
+            .collect::<String>();
+        if entry.task.content.chars().count() > RECENT_ITEM_MAX_CHARS {
+            content.push('…');
+        }
+        recent_submenu.append(&MenuItem::with_id(
+            app_handle,
+            format!("{RECENT_ITEM_ID_PREFIX}{}", entry.id),
+            format!("{} {content}", get_status_icon(entry.status)),
+            entry.status == CaptureStatus::Sent,
+            None::<String>,
+        )?)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the symbol marking the send status of a task in the "Recent" submenu.
+fn get_status_icon(status: CaptureStatus) -> &'static str {
+    match status {
+        CaptureStatus::Sent => "✓",
+        CaptureStatus::Queued => "⏳",
+        CaptureStatus::Failed => "✗",
+        CaptureStatus::Undone => "↶",
+    }
+}
+
+/// Logs the user out without blocking the event loop, as the tokens are revoked first.
+///
+/// A refusal, e.g., because captured tasks have not been added yet, is shown in a notification.
+fn log_out(everywhere: bool, app_handle: &AppHandle) {
+    let owned_app_handle = app_handle.to_owned();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = auth::revoke_and_log_out(everywhere, &owned_app_handle).await {
+            log::error!("{e:?}");
+            let body = format!("{e:#}");
+            let _ = notification::send(
+                "Failed to log out",
+                &body,
+                &[],
+                NotificationContext::default(),
+                &owned_app_handle,
+            )
+            .await
+            .inspect_err(|e| log::error!("{e:?}"));
+        }
+    });
+}
+
 /// Handles the authentication state change event.
-fn on_authentication_state_change(owned_tray_menu: &Menu<Wry>, event: Event) -> AppResult<()> {
-    for menu_id in [MenuId::QuickAdd, MenuId::LogOut] {
-        owned_tray_menu
-            .get(&menu_id.to_string())
-            .and_then(|menu_item| menu_item.as_menuitem().cloned())
-            .ok_or(format_err!(
-                "Failed to retrieve the {:?} menu item.",
-                menu_id
-            ))?
-            .set_enabled(serde_json::from_str::<bool>(event.payload())?)?;
+fn on_authentication_state_change(
+    app_handle: &AppHandle,
+    owned_tray_menu: &Menu<Wry>,
+    event: Event,
+) -> AppResult<()> {
+    let user_authenticated = serde_json::from_str::<bool>(event.payload())?;
+    for menu_id in [MenuId::QuickAdd, MenuId::LogOut, MenuId::LogOutEverywhere] {
+        get_menu_item(owned_tray_menu, menu_id)?.set_enabled(user_authenticated)?;
     }
+    get_menu_item(owned_tray_menu, MenuId::UndoLastTask)?
+        .set_enabled(user_authenticated && settings::get(app_handle)?.undo)?;
 
     Ok(())
 }
+
+/// Retrieves a plain menu item of the tray menu.
+fn get_menu_item(owned_tray_menu: &Menu<Wry>, menu_id: MenuId) -> AppResult<MenuItem<Wry>> {
+    owned_tray_menu
+        .get(&menu_id.to_string())
+        .and_then(|menu_item| menu_item.as_menuitem().cloned())
+        .ok_or(format_err!(
+            "Failed to retrieve the {:?} menu item.",
+            menu_id
+        ))
+}
@@ -12,6 +12,8 @@ enum WindowLabel {
     QuickAdd,
     /// The Authentication window for logging into Todoist.
     Authentication,
+    /// The Settings window for managing user preferences.
+    Settings,
 }
 
 impl fmt::Display for WindowLabel {
@@ -19,6 +21,7 @@ impl fmt::Display for WindowLabel {
         match self {
             WindowLabel::QuickAdd => write!(f, "quick-add"),
             WindowLabel::Authentication => write!(f, "authentication"),
+            WindowLabel::Settings => write!(f, "settings"),
         }
     }
 }
@@ -28,6 +31,7 @@ impl From<&str> for WindowLabel {
         match value {
             "quick-add" => WindowLabel::QuickAdd,
             "authentication" => WindowLabel::Authentication,
+            "settings" => WindowLabel::Settings,
             _ => panic!("Unknown window label: {}", value),
         }
     }
@@ -96,6 +100,22 @@ pub fn init_authentication_window(app_handle: &AppHandle, minimize: bool) -> App
     Ok(())
 }
 
+/// Opens the Settings window.
+///
+/// If the Settings window already exists, it is shown. Otherwise, a new one is created.
+pub fn init_settings_window(app_handle: &AppHandle) -> AppResult<()> {
+    log::info!("Opening the Settings window...");
+
+    let window = app_handle
+        .get_webview_window(&WindowLabel::Settings.to_string())
+        .ok_or(tauri::Error::WebviewNotFound)
+        .or_else(|_| create_window(WindowLabel::Settings, app_handle))?;
+    window.show()?;
+    window.set_focus()?;
+
+    Ok(())
+}
+
 /// Switches the application to the Quick-Add dialog.
 ///
 /// This function destroys all existing webview windows except the Quick-Add dialog,
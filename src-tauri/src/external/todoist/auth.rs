@@ -1,78 +1,385 @@
 use crate::external::todoist;
-use crate::external::todoist::sdk::PermissionScope;
+use crate::external::todoist::sdk::{AuthCallbackError, AuthCallbackResponse, PermissionScope};
+use crate::ipc::deeplink;
 use crate::ipc::events::CustomEvent;
 use crate::shared::error::AppResult;
 use crate::shared::state::AppState;
 use crate::shared::storage;
-use crate::shared::storage::key::StorageKey;
-use anyhow::{ensure, Context};
+use crate::shared::storage::credentials::{TokenBundle, TokenKind};
+use anyhow::{bail, ensure, format_err, Context};
+use serde::Serialize;
 use serde_json::json;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use tauri::{AppHandle, Emitter, Manager, State};
+use std::future::Future;
+use std::net::{Ipv4Addr, TcpListener};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Listener, Manager, State};
 use tauri_plugin_opener::OpenerExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
 
 /// Refresh the access token this many seconds before it actually expires.
 const REFRESH_EARLY_SECS: u64 = 60;
 
+/// The upper bound of the time between two checks of the token expiry by the background refresh.
+const REFRESH_CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// The delay before retrying a failed background refresh.
+const REFRESH_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Serializes the token refreshes, since Todoist rotates the refresh token on every refresh.
+static REFRESH_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// The path of the OAuth callback on the loopback listener.
+const LOOPBACK_CALLBACK_PATH: &str = "/oauth";
+
+/// The maximum size of an HTTP request head accepted by the loopback listener.
+const LOOPBACK_MAX_REQUEST_SIZE: usize = 8 * 1024;
+
+/// Represents an authentication flow waiting for its OAuth callback.
+///
+/// It is taken out of the application state as soon as the callback arrives, or
+/// when it is cancelled or expires, so a stale flow can never be completed.
+#[derive(Debug)]
+pub struct AuthAttempt {
+    /// The CSRF state sent with the authorization request, which also identifies the attempt.
+    csrf_state: String,
+    pkce_verifier: String,
+    /// The permission scopes requested from the user.
+    requested_scopes: Vec<PermissionScope>,
+    /// The redirect URI of the loopback listener, if one is used instead of the deep link.
+    redirect_uri: Option<String>,
+    started_at: Instant,
+    timeout: Duration,
+    /// The task waiting for the callback on the loopback listener, if any.
+    loopback_listener: Option<JoinHandle<()>>,
+}
+
+impl AuthAttempt {
+    /// Checks whether the attempt has been waiting longer than allowed.
+    fn is_expired(&self) -> bool {
+        self.started_at.elapsed() > self.timeout
+    }
+
+    /// Shuts the loopback listener of the attempt down, if any.
+    pub fn abort(&self) {
+        if let Some(loopback_listener) = &self.loopback_listener {
+            loopback_listener.abort();
+        }
+    }
+}
+
+/// Represents the progress of an authentication flow, as emitted with the
+/// `AuthenticationProgress` event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuthProgress {
+    /// The authorization page has been opened in the browser.
+    WaitingForBrowser,
+    /// The callback has arrived and its code is being exchanged for the tokens.
+    ExchangingCode,
+    /// The user is logged in.
+    Done,
+    /// The flow failed; the details are emitted with the `AuthenticationError` event.
+    Failed,
+    /// The flow was cancelled by the user.
+    Cancelled,
+    /// The callback did not arrive in time.
+    Expired,
+}
+
+/// Represents a failed authentication flow, as emitted with the `AuthenticationError` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthenticationError {
+    /// The OAuth error code, e.g., `access_denied`, or `None` for any other failure.
+    pub code: Option<String>,
+    /// The human-readable description of the failure.
+    pub message: String,
+}
+
+impl From<&anyhow::Error> for AuthenticationError {
+    fn from(error: &anyhow::Error) -> Self {
+        match error.downcast_ref::<AuthCallbackError>() {
+            Some(callback_error) => Self {
+                code: Some(callback_error.error.to_owned()),
+                message: callback_error.to_string(),
+            },
+            None => Self {
+                code: None,
+                message: "Logging in to Todoist failed; please try again".to_owned(),
+            },
+        }
+    }
+}
+
 /// Initiates the Todoist authentication flow.
 ///
 /// This command generates a CSRF state, constructs the Todoist authorization URL,
 /// and opens it in the user's default browser. The CSRF state is stored in the
 /// application state for later verification.
+///
+/// The callback is received through the `capturist://` deep link if a handler for it is
+/// available. Otherwise, e.g., in a Snap or Flatpak without a working scheme handler,
+/// a one-shot loopback listener on `127.0.0.1` is used as the redirect URI.
 pub fn start_authentication(
     app_handle: &AppHandle,
     app_state: &State<'_, AppState>,
 ) -> AppResult<()> {
+    let mut permission_scopes = vec![PermissionScope::TaskAdd, PermissionScope::DataRead];
+    // Deleting tasks is only requested from users who opted into undoing them.
+    if storage::settings::get(app_handle)?.undo {
+        permission_scopes.push(PermissionScope::DataDelete);
+    }
+    start_authorization_flow(permission_scopes, app_handle, app_state)
+}
+
+/// Re-runs the authentication flow to grant additional permission scopes to the
+/// current session, without logging out first.
+///
+/// The scopes granted so far are requested again, so the new token is a superset of the old one.
+pub fn request_additional_scopes(
+    scopes: &[PermissionScope],
+    app_handle: &AppHandle,
+    app_state: &State<'_, AppState>,
+) -> AppResult<()> {
+    ensure!(
+        get_token_kind(app_handle)? != Some(TokenKind::Personal),
+        "Personal API tokens already have all the permissions"
+    );
+    let mut permission_scopes = get_granted_scopes(app_handle)?;
+    for scope in scopes {
+        if !permission_scopes.contains(scope) {
+            permission_scopes.push(*scope);
+        }
+    }
+    log::info!("Requesting additional scopes: {scopes:?}");
+    start_authorization_flow(permission_scopes, app_handle, app_state)
+}
+
+/// Returns the permission scopes granted to the stored token.
+pub fn get_granted_scopes(app_handle: &AppHandle) -> AppResult<Vec<PermissionScope>> {
+    let scopes = storage::credentials::find(app_handle)?
+        .map(|bundle| bundle.scopes)
+        .unwrap_or_default();
+    Ok(scopes)
+}
+
+/// Checks whether the stored token has been granted the given permission scope.
+///
+/// Personal API tokens have all the permissions of the user.
+pub fn has_scope(scope: PermissionScope, app_handle: &AppHandle) -> AppResult<bool> {
+    if get_token_kind(app_handle)? == Some(TokenKind::Personal) {
+        return Ok(true);
+    }
+    let granted = get_granted_scopes(app_handle)?
+        .iter()
+        .any(|granted_scope| granted_scope.includes(scope));
+    Ok(granted)
+}
+
+/// Cancels the authentication flow in progress, if any, so the user can start over.
+pub fn cancel_authentication(app_handle: &AppHandle) -> AppResult<()> {
+    let attempt = app_handle
+        .state::<AppState>()
+        .auth_attempt
+        .lock()
+        .unwrap()
+        .take();
+    if let Some(attempt) = attempt {
+        log::info!("Cancelling the authentication flow...");
+        attempt.abort();
+        emit_progress(AuthProgress::Cancelled, app_handle)?;
+    }
+    Ok(())
+}
+
+/// Opens the Todoist authorization page in the browser, asking for the given scopes.
+///
+/// Any flow still in progress is replaced. The new one expires after the timeout
+/// configured in the settings.
+fn start_authorization_flow(
+    permission_scopes: Vec<PermissionScope>,
+    app_handle: &AppHandle,
+    app_state: &State<'_, AppState>,
+) -> AppResult<()> {
+    if let Some(previous_attempt) = app_state.auth_attempt.lock().unwrap().take() {
+        previous_attempt.abort();
+    }
+
+    let timeout_minutes = storage::settings::get(app_handle)?.authentication_timeout_minutes;
+    let timeout = Duration::from_secs(u64::from(timeout_minutes.max(1)) * 60);
+    let (redirect_uri, loopback_listener) = if deeplink::is_scheme_handler_available() {
+        (None, None)
+    } else {
+        log::info!("No URL scheme handler available; Using a loopback redirect instead...");
+        let (redirect_uri, loopback_listener) = start_loopback_listener(app_handle)?;
+        (Some(redirect_uri), Some(loopback_listener))
+    };
     let client_id = todoist::TODOIST_CLIENT_ID;
-    let permission_scopes = &[PermissionScope::TaskAdd];
     let csrf_state = todoist::sdk::get_auth_state_parameter();
     let pkce_verifier = todoist::sdk::get_pkce_verifier();
     let pkce_challenge = todoist::sdk::get_pkce_challenge(&pkce_verifier);
-    *app_state.csrf_state.lock().unwrap() = Some(csrf_state.to_owned());
-    *app_state.pkce_verifier.lock().unwrap() = Some(pkce_verifier);
     let url = todoist::sdk::get_authorization_url(
         client_id,
-        permission_scopes,
+        &permission_scopes,
         &csrf_state,
         &pkce_challenge,
+        redirect_uri.as_deref(),
     )?;
+    *app_state.auth_attempt.lock().unwrap() = Some(AuthAttempt {
+        csrf_state: csrf_state.to_owned(),
+        pkce_verifier,
+        requested_scopes: permission_scopes,
+        redirect_uri,
+        started_at: Instant::now(),
+        timeout,
+        loopback_listener,
+    });
+    spawn_expiry(csrf_state, timeout, app_handle);
     app_handle.opener().open_url(url.as_str(), None::<&str>)?;
+    emit_progress(AuthProgress::WaitingForBrowser, app_handle)?;
 
     Ok(())
 }
 
+/// Expires the authentication flow identified by its CSRF state after the timeout,
+/// unless it has completed or been replaced by then.
+fn spawn_expiry(csrf_state: String, timeout: Duration, app_handle: &AppHandle) {
+    let owned_app_handle = app_handle.to_owned();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(timeout).await;
+        let app_state = owned_app_handle.state::<AppState>();
+        let mut auth_attempt = app_state.auth_attempt.lock().unwrap();
+        if auth_attempt
+            .as_ref()
+            .is_some_and(|attempt| attempt.csrf_state == csrf_state)
+        {
+            log::warn!("The authentication flow expired.");
+            if let Some(attempt) = auth_attempt.take() {
+                attempt.abort();
+            }
+            let _ = emit_progress(AuthProgress::Expired, &owned_app_handle)
+                .inspect_err(|e| log::error!("{e:?}"));
+        }
+    });
+}
+
+/// Emits the progress of the authentication flow.
+fn emit_progress(progress: AuthProgress, app_handle: &AppHandle) -> AppResult<()> {
+    log::info!("Authentication progress: {progress:?}");
+    app_handle.emit(&CustomEvent::AuthenticationProgress.to_string(), progress)?;
+    Ok(())
+}
+
 /// This async function is spawned as a new task when a deep link is received.
 /// It handles the entire backend authentication flow.
 pub async fn authenticate(url: &tauri::Url, app_handle: &AppHandle) -> AppResult<()> {
-    let query = url.query().context("Missing query parameters")?;
+    let query = url.query().unwrap_or_default();
+    authenticate_with_callback_query(query, app_handle).await
+}
+
+/// Handles the backend authentication flow for the query parameters of an OAuth callback.
+///
+/// The CSRF state and the PKCE verifier are validated the same way regardless of
+/// whether the callback arrived through a deep link or the loopback listener.
+/// They are reset once the callback of the attempt in progress arrives, and a failure
+/// is reported with the `AuthenticationError` event, so the user can retry cleanly.
+async fn authenticate_with_callback_query(query: &str, app_handle: &AppHandle) -> AppResult<()> {
+    let result = exchange_callback_query(query, app_handle).await;
+    match &result {
+        Ok(()) => emit_progress(AuthProgress::Done, app_handle)?,
+        Err(e) => {
+            app_handle.emit(
+                &CustomEvent::AuthenticationError.to_string(),
+                AuthenticationError::from(e),
+            )?;
+            emit_progress(AuthProgress::Failed, app_handle)?;
+        }
+    }
+    result
+}
+
+/// Validates the OAuth callback and exchanges its code for the tokens.
+async fn exchange_callback_query(query: &str, app_handle: &AppHandle) -> AppResult<()> {
     let payload = serde_urlencoded::from_str::<todoist::sdk::AuthCallbackResponse>(query)
         .context("Invalid query parameters")?;
-    let stored_state = app_handle
-        .state::<AppState>()
-        .csrf_state
-        .lock()
-        .unwrap()
-        .take()
-        .unwrap_or_default();
-    let code_verifier = app_handle
-        .state::<AppState>()
-        .pkce_verifier
-        .lock()
-        .unwrap()
-        .take()
-        .unwrap_or_default();
+    // The attempt is only taken by its own callback, so a foreign one cannot cancel it.
+    let state = app_handle.state::<AppState>();
+    let attempt = {
+        let mut attempt = state.auth_attempt.lock().unwrap();
+        ensure!(
+            attempt.is_some(),
+            "No login is in progress; it may have been cancelled or expired"
+        );
+        attempt
+            .take_if(|attempt| payload.state() == Some(attempt.csrf_state.as_str()))
+            .context("OAuth state mismatch. Potential CSRF attack detected")?
+    };
+    ensure!(!attempt.is_expired(), "The login expired; please try again");
+    let code = match payload {
+        AuthCallbackResponse::Success { code, .. } => code,
+        AuthCallbackResponse::Error(error) => return Err(error.into()),
+    };
+
+    emit_progress(AuthProgress::ExchangingCode, app_handle)?;
+    let response = todoist::sdk::get_auth_token(
+        todoist::TODOIST_CLIENT_ID,
+        &code,
+        &attempt.pkce_verifier,
+        attempt.redirect_uri.as_deref(),
+    )
+    .await?;
+    // Todoist may not report the granted scopes, in which case all the requested ones were granted.
+    let granted_scopes = match &response.scope {
+        Some(scope) => todoist::sdk::parse_scopes(scope)?,
+        None => attempt.requested_scopes,
+    };
+    log::info!("Granted scopes: {granted_scopes:?}");
+    // Authorizing additional scopes must not be taken for a new login, e.g., by the windows.
+    let authenticated = *app_handle.state::<AppState>().authenticated.lock().unwrap();
+    store_tokens(&response, granted_scopes.to_owned(), None, app_handle)?;
+    if authenticated {
+        app_handle.emit(&CustomEvent::Scopes.to_string(), granted_scopes)?;
+    } else {
+        app_handle.emit(&CustomEvent::Authentication.to_string(), json!(true))?;
+    }
+
+    Ok(())
+}
+
+/// Logs in with a personal API token copied from the Todoist settings.
+///
+/// This is an alternative to the OAuth flow for machines that cannot complete a
+/// browser round-trip. The token is validated against the API before it is stored.
+/// Personal tokens never expire, so they have no expiry or refresh token.
+pub async fn log_in_with_personal_token(token: &str, app_handle: &AppHandle) -> AppResult<()> {
+    let token = token.trim();
+    ensure!(
+        !token.is_empty(),
+        "The personal API token must not be empty"
+    );
 
-    ensure!(payload.state == stored_state,
-        "OAuth state mismatch. Potential CSRF attack detected. URL: {:?}, State: {}, Stored State: {}",
-        url,
-        payload.state,
-        stored_state
+    let user = todoist::sdk::get_user(token).await.map_err(|e| {
+        if todoist::sdk::is_unauthorized(&e) {
+            format_err!("The personal API token is invalid")
+        } else {
+            e
+        }
+    })?;
+    log::info!(
+        "Logging in with the personal API token of user {}...",
+        user.id
     );
 
-    let response =
-        todoist::sdk::get_auth_token(todoist::TODOIST_CLIENT_ID, &payload.code, &code_verifier)
-            .await?;
-    store_tokens(&response, app_handle)?;
+    let bundle = TokenBundle {
+        kind: TokenKind::Personal,
+        access_token: token.to_owned(),
+        refresh_token: None,
+        expires_at: None,
+        scopes: Vec::new(),
+    };
+    storage::credentials::set(&bundle, app_handle)?;
     app_handle.emit(&CustomEvent::Authentication.to_string(), json!(true))?;
 
     Ok(())
@@ -80,67 +387,434 @@ pub async fn authenticate(url: &tauri::Url, app_handle: &AppHandle) -> AppResult
 
 /// Returns a Todoist access token that is valid for the near future, refreshing
 /// it first if it is about to expire or has already expired.
+///
+/// Personal API tokens are returned as is, since they never expire.
 pub async fn get_valid_access_token(app_handle: &AppHandle) -> AppResult<String> {
-    let expires_at_secs = storage::secure::find(StorageKey::TodoistTokenExpiresAt, app_handle)?
-        .context("No Todoist token expiration entry; please sign in again")
-        .inspect_err(|_| log_out(app_handle).unwrap())?
-        .parse::<u64>()
-        .context("Invalid Todoist token expiration entry; please sign in again")
-        .inspect_err(|_| log_out(app_handle).unwrap())?;
+    let bundle = get_token_bundle(app_handle)?;
+    if bundle.kind == TokenKind::Personal {
+        return Ok(bundle.access_token);
+    }
+
+    if is_expiring(get_token_expires_at(&bundle, app_handle)?)? {
+        refresh_expiring_token(app_handle).await?;
+        return Ok(get_token_bundle(app_handle)?.access_token);
+    }
+    Ok(bundle.access_token)
+}
+
+/// Runs an authorized Todoist API request with a valid access token.
+///
+/// If the request fails with `401 Unauthorized` the access token has expired
+/// mid-session, so it is refreshed once and the request is retried with the new one.
+pub async fn with_valid_access_token<T, F, Fut>(app_handle: &AppHandle, request: F) -> AppResult<T>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = AppResult<T>>,
+{
+    let access_token = get_valid_access_token(app_handle).await?;
+    match request(access_token.to_owned()).await {
+        Err(e) if todoist::sdk::is_unauthorized(&e) => {
+            log::warn!("Hit error 401; Refreshing the token...");
+            refresh_rejected_token(&access_token, app_handle).await?;
+            request(get_valid_access_token(app_handle).await?).await
+        }
+        result => result,
+    }
+}
+
+/// Sets up the background refresh of the OAuth access token.
+///
+/// The token is refreshed shortly before it expires, so the first request after a
+/// long idle period does not have to wait for it. The expiry is re-checked
+/// periodically and right after logging in or resuming from suspend, since timers
+/// do not advance while the system is suspended.
+pub fn set_up_token_refresh(app_handle: &AppHandle) {
+    log::info!("Setting up the token refresh...");
+
+    for event in [
+        CustomEvent::Authentication,
+        CustomEvent::Scopes,
+        CustomEvent::Resume,
+    ] {
+        let owned_app_handle = app_handle.to_owned();
+        app_handle.listen(event.to_string(), move |_| {
+            owned_app_handle
+                .state::<AppState>()
+                .token_refresh_notify
+                .notify_one();
+        });
+    }
+
+    let app_handle = app_handle.to_owned();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let mut delay = get_refresh_delay(&app_handle)
+                .inspect_err(|e| log::error!("{e:?}"))
+                .unwrap_or(REFRESH_CHECK_INTERVAL);
+            if delay.is_zero() {
+                log::info!("Refreshing the token ahead of its expiry...");
+                match refresh_expiring_token(&app_handle).await {
+                    // The expiry is only checked again after a pause, so a token that still
+                    // looks expiring, e.g., due to a skewed clock, is not refreshed in a loop.
+                    Ok(()) => {
+                        tokio::time::sleep(REFRESH_RETRY_DELAY).await;
+                        continue;
+                    }
+                    Err(e) => {
+                        log::error!("{e:?}");
+                        delay = REFRESH_RETRY_DELAY;
+                    }
+                }
+            }
 
+            let state = app_handle.state::<AppState>();
+            tokio::select! {
+                _ = tokio::time::sleep(delay.min(REFRESH_CHECK_INTERVAL)) => {}
+                _ = state.token_refresh_notify.notified() => {}
+            }
+        }
+    });
+}
+
+/// Returns how long to wait before the stored token has to be refreshed.
+///
+/// Without an OAuth session, the next check is simply scheduled after the check interval.
+fn get_refresh_delay(app_handle: &AppHandle) -> AppResult<Duration> {
+    if !*app_handle.state::<AppState>().authenticated.lock().unwrap() {
+        return Ok(REFRESH_CHECK_INTERVAL);
+    }
+    let bundle = get_token_bundle(app_handle)?;
+    if bundle.kind == TokenKind::Personal {
+        return Ok(REFRESH_CHECK_INTERVAL);
+    }
+
+    let refresh_at_secs =
+        get_token_expires_at(&bundle, app_handle)?.saturating_sub(REFRESH_EARLY_SECS);
     let now_secs = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    Ok(Duration::from_secs(
+        refresh_at_secs.saturating_sub(now_secs),
+    ))
+}
 
-    if expires_at_secs <= now_secs + REFRESH_EARLY_SECS {
-        refresh_stored_token(app_handle).await?;
+/// Returns the stored credentials, logging out the user if there are none.
+fn get_token_bundle(app_handle: &AppHandle) -> AppResult<TokenBundle> {
+    let Some(bundle) = storage::credentials::find(app_handle)? else {
+        log_out(app_handle)?;
+        bail!("No Todoist token found; please sign in again");
+    };
+    Ok(bundle)
+}
+
+/// Returns the Unix timestamp (seconds) at which the given OAuth access token expires.
+fn get_token_expires_at(bundle: &TokenBundle, app_handle: &AppHandle) -> AppResult<u64> {
+    let Some(expires_at) = bundle.expires_at else {
+        log_out(app_handle)?;
+        bail!("No Todoist token expiration entry; please sign in again");
+    };
+    Ok(expires_at)
+}
+
+/// Checks whether a token expiring at the given Unix timestamp (seconds) should be refreshed.
+fn is_expiring(expires_at_secs: u64) -> AppResult<bool> {
+    let now_secs = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    Ok(expires_at_secs <= now_secs + REFRESH_EARLY_SECS)
+}
+
+/// Refreshes the stored token if it is about to expire.
+///
+/// The expiry is checked again behind the lock, so concurrent callers refresh it only once.
+async fn refresh_expiring_token(app_handle: &AppHandle) -> AppResult<()> {
+    let _guard = REFRESH_LOCK.lock().await;
+    let bundle = get_token_bundle(app_handle)?;
+    if is_expiring(get_token_expires_at(&bundle, app_handle)?)? {
+        refresh_stored_token(bundle, app_handle).await?;
     }
+    Ok(())
+}
 
-    storage::secure::find(StorageKey::TodoistToken, app_handle)?
-        .context("No Todoist token found; please sign in again")
-        .inspect_err(|_| log_out(app_handle).unwrap())
+/// Refreshes the stored token after it has been rejected by the API, unless it has
+/// already been replaced by a concurrent refresh.
+async fn refresh_rejected_token(rejected_token: &str, app_handle: &AppHandle) -> AppResult<()> {
+    let _guard = REFRESH_LOCK.lock().await;
+    let bundle = get_token_bundle(app_handle)?;
+    if bundle.access_token == rejected_token {
+        refresh_stored_token(bundle, app_handle).await?;
+    }
+    Ok(())
 }
 
 /// Refreshes the stored access token using the stored refresh token.
 ///
 /// Todoist rotates the refresh token on every refresh, so the response replaces
-/// the previously stored one.
-pub async fn refresh_stored_token(app_handle: &AppHandle) -> AppResult<()> {
-    let refresh_token = storage::secure::find(StorageKey::TodoistRefreshToken, app_handle)?
-        .context("No Todoist refresh token available; please sign in again")
-        .inspect_err(|_| log_out(app_handle).unwrap())?;
-    let response =
-        todoist::sdk::refresh_access_token(todoist::TODOIST_CLIENT_ID, &refresh_token).await?;
-    store_tokens(&response, app_handle)?;
+/// the previously stored one. A personal API token cannot be refreshed, so it has
+/// been revoked if this is ever needed, and the user is logged out. So are they
+/// if Todoist rejects the refresh token, e.g., because it was revoked.
+///
+/// The caller must hold the refresh lock, so a rotated refresh token is never used twice.
+async fn refresh_stored_token(bundle: TokenBundle, app_handle: &AppHandle) -> AppResult<()> {
+    if bundle.kind == TokenKind::Personal {
+        log_out(app_handle)?;
+        bail!("The personal API token is no longer valid; please sign in again");
+    }
+
+    let Some(refresh_token) = bundle.refresh_token else {
+        log_out(app_handle)?;
+        bail!("No Todoist refresh token available; please sign in again");
+    };
+    let response = match todoist::sdk::refresh_access_token(
+        todoist::TODOIST_CLIENT_ID,
+        &refresh_token,
+    )
+    .await
+    {
+        Err(e) if todoist::sdk::is_rejected(&e) => {
+            log_out(app_handle)?;
+            return Err(e.context("The Todoist session has expired; please sign in again"));
+        }
+        result => result?,
+    };
+    store_tokens(&response, bundle.scopes, Some(refresh_token), app_handle)?;
+    log::info!("The Todoist access token has been refreshed.");
     Ok(())
 }
 
+/// Logs out the user after revoking the stored tokens at Todoist.
+///
+/// The revocation is best-effort: if it fails, e.g., while offline, the error is
+/// logged and the local session is cleared anyway. If `everywhere` is set, all the
+/// data cached by the application is cleared as well, which is refused while any
+/// captured task has not been added to Todoist yet, so it is never lost.
+pub async fn revoke_and_log_out(everywhere: bool, app_handle: &AppHandle) -> AppResult<()> {
+    if everywhere {
+        ensure_outbox_delivered(app_handle).await?;
+    }
+    let _ = revoke_stored_tokens(app_handle)
+        .await
+        .inspect_err(|e| log::warn!("Failed to revoke the Todoist tokens: {e:?}"));
+    if everywhere {
+        clear_cached_data(app_handle)?;
+    }
+    log_out(app_handle)
+}
+
 /// Logs out the user by clearing user data and emitting an authentication event.
 pub fn log_out(app_handle: &AppHandle) -> AppResult<()> {
-    storage::secure::delete(StorageKey::TodoistToken, app_handle)?;
-    storage::secure::delete(StorageKey::TodoistTokenExpiresAt, app_handle)?;
-    storage::secure::delete(StorageKey::TodoistRefreshToken, app_handle)?;
+    storage::credentials::delete(app_handle)?;
     app_handle.emit(&CustomEvent::Authentication.to_string(), json!(false))?;
 
     Ok(())
 }
 
-/// Persists the access token, its expiry and the refresh token from a token response.
+/// Revokes the stored access and refresh tokens at Todoist.
+///
+/// Personal API tokens are left untouched, since they can only be reset in the Todoist settings.
+async fn revoke_stored_tokens(app_handle: &AppHandle) -> AppResult<()> {
+    let Some(bundle) = storage::credentials::find(app_handle)? else {
+        return Ok(());
+    };
+    if bundle.kind == TokenKind::Personal {
+        log::info!("Skipping the revocation of the personal API token.");
+        return Ok(());
+    }
+
+    // Both tokens are revoked even if one of the revocations fails.
+    let refresh_result = match &bundle.refresh_token {
+        Some(refresh_token) => {
+            todoist::sdk::revoke_token(todoist::TODOIST_CLIENT_ID, refresh_token, "refresh_token")
+                .await
+        }
+        None => Ok(()),
+    };
+    let access_result = todoist::sdk::revoke_token(
+        todoist::TODOIST_CLIENT_ID,
+        &bundle.access_token,
+        "access_token",
+    )
+    .await;
+    match (refresh_result, access_result) {
+        (Ok(()), Ok(())) => {
+            log::info!("The Todoist tokens have been revoked.");
+            Ok(())
+        }
+        (Err(e), Ok(())) => Err(e.context("Failed to revoke the refresh token")),
+        (Ok(()), Err(e)) => Err(e.context("Failed to revoke the access token")),
+        (Err(refresh_error), Err(access_error)) => Err(format_err!(
+            "Failed to revoke the refresh token: {refresh_error:#}; \
+            Failed to revoke the access token: {access_error:#}"
+        )),
+    }
+}
+
+/// Delivers the tasks waiting in the outbox, failing if any of them is left, e.g.,
+/// because it was rejected or Todoist is not reachable.
+async fn ensure_outbox_delivered(app_handle: &AppHandle) -> AppResult<()> {
+    let _ = todoist::task::deliver_outbox(app_handle)
+        .await
+        .inspect_err(|e| log::error!("{e:?}"));
+    let summary = storage::outbox::summarize(app_handle)?;
+    ensure!(
+        summary.pending + summary.failed == 0,
+        "{} captured task(s) have not been added to Todoist yet; \
+        retry or discard them in the settings before clearing the data",
+        summary.pending + summary.failed
+    );
+    Ok(())
+}
+
+/// Clears all the user data cached by the application.
+///
+/// The outbox must have been delivered before.
+fn clear_cached_data(app_handle: &AppHandle) -> AppResult<()> {
+    log::info!("Clearing the cached data...");
+    storage::history::clear(app_handle)?;
+    storage::resources::clear(app_handle)
+}
+
+/// Starts a one-shot HTTP listener on an ephemeral loopback port to receive the OAuth callback.
+///
+/// The listener shuts down after the first callback, or when it is aborted because the
+/// authentication flow was cancelled or expired.
+/// Returns the redirect URI pointing to the listener, and the task running it.
+fn start_loopback_listener(app_handle: &AppHandle) -> AppResult<(String, JoinHandle<()>)> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    listener.set_nonblocking(true)?;
+    let redirect_uri = format!("http://{}{LOOPBACK_CALLBACK_PATH}", listener.local_addr()?);
+    log::info!("Listening for the OAuth callback on {redirect_uri}...");
+
+    let owned_app_handle = app_handle.to_owned();
+    let loopback_listener = tauri::async_runtime::spawn(async move {
+        let _ = accept_loopback_callback(listener, &owned_app_handle)
+            .await
+            .inspect_err(|e| log::error!("{e:?}"));
+        log::info!("The loopback listener has shut down.");
+    });
+
+    Ok((redirect_uri, loopback_listener))
+}
+
+/// Accepts connections on the loopback listener until the OAuth callback arrives,
+/// then authenticates with it and answers the browser with the outcome.
+///
+/// Callbacks that do not carry the CSRF state of the current attempt are answered
+/// without touching the attempt, so no other local process can end the login.
+async fn accept_loopback_callback(listener: TcpListener, app_handle: &AppHandle) -> AppResult<()> {
+    let listener = tokio::net::TcpListener::from_std(listener)?;
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let query = match read_loopback_callback_query(&mut stream).await {
+            Ok(query) => query,
+            Err(e) => {
+                log::warn!("Ignoring an invalid request on the loopback listener: {e:?}");
+                let _ = write_loopback_response(&mut stream, "404 Not Found", "Not found.").await;
+                continue;
+            }
+        };
+        if !matches_current_attempt(&query, app_handle) {
+            log::warn!("Ignoring an OAuth callback with an unexpected state.");
+            let _ =
+                write_loopback_response(&mut stream, "400 Bad Request", "Unexpected state.").await;
+            continue;
+        }
+
+        let result = authenticate_with_callback_query(&query, app_handle).await;
+        let message = match &result {
+            Ok(_) => "You are logged in to Capturist. You can close this tab now.".to_owned(),
+            Err(e) => format!(
+                "{}. Please go back to the app and try again.",
+                AuthenticationError::from(e).message
+            ),
+        };
+        write_loopback_response(&mut stream, "200 OK", &message).await?;
+        return result;
+    }
+}
+
+/// Checks whether the query of an OAuth callback carries the CSRF state of the
+/// authentication flow in progress, without taking the attempt.
+fn matches_current_attempt(query: &str, app_handle: &AppHandle) -> bool {
+    let Ok(payload) = serde_urlencoded::from_str::<AuthCallbackResponse>(query) else {
+        return false;
+    };
+    app_handle
+        .state::<AppState>()
+        .auth_attempt
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|attempt| payload.state() == Some(attempt.csrf_state.as_str()))
+}
+
+/// Reads an HTTP request from the loopback listener and returns the query of
+/// the OAuth callback, failing for any other request.
+async fn read_loopback_callback_query(stream: &mut TcpStream) -> AppResult<String> {
+    let mut buffer = Vec::new();
+    while !buffer.windows(4).any(|window| window == b"\r\n\r\n") {
+        ensure!(
+            buffer.len() < LOOPBACK_MAX_REQUEST_SIZE,
+            "Request head too large"
+        );
+        let mut chunk = [0; 1024];
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            bail!("Connection closed before the request was complete");
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    let request = String::from_utf8_lossy(&buffer);
+    let target = match request.split_whitespace().collect::<Vec<_>>()[..] {
+        ["GET", target, ..] => target,
+        _ => bail!("Unexpected request line"),
+    };
+    let url = url::Url::parse(&format!("http://{}{target}", Ipv4Addr::LOCALHOST))?;
+    ensure!(url.path() == LOOPBACK_CALLBACK_PATH, "Unexpected path");
+
+    url.query()
+        .map(str::to_owned)
+        .context("Missing query parameters")
+}
+
+/// Writes a minimal plain-text HTTP response to the browser.
+async fn write_loopback_response(
+    stream: &mut TcpStream,
+    status: &str,
+    message: &str,
+) -> AppResult<()> {
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{message}",
+        message.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Persists the tokens from a token response along with the permission scopes granted to them.
+///
+/// The refresh token is kept if the response does not rotate it. All the credentials
+/// are written at once, so a failure never leaves an inconsistent session behind.
 fn store_tokens(
     response: &todoist::sdk::AccessTokenResponse,
+    scopes: Vec<PermissionScope>,
+    refresh_token: Option<String>,
     app_handle: &AppHandle,
 ) -> AppResult<()> {
-    storage::secure::set(StorageKey::TodoistToken, &response.access_token, app_handle)?;
     let expires_at = SystemTime::now()
         .duration_since(UNIX_EPOCH)?
         .checked_add(Duration::from_secs(response.expires_in))
         .with_context(|| "Expires timestamp overflow")?
         .as_secs();
-    storage::secure::set(
-        StorageKey::TodoistTokenExpiresAt,
-        &expires_at.to_string(),
-        app_handle,
-    )?;
-    if let Some(refresh_token) = &response.refresh_token {
-        storage::secure::set(StorageKey::TodoistRefreshToken, refresh_token, app_handle)?;
-    }
-    Ok(())
+    let bundle = TokenBundle {
+        kind: TokenKind::OAuth,
+        access_token: response.access_token.to_owned(),
+        refresh_token: response.refresh_token.to_owned().or(refresh_token),
+        expires_at: Some(expires_at),
+        scopes,
+    };
+    storage::credentials::set(&bundle, app_handle)
+}
+
+/// Returns the kind of the stored token, if any.
+fn get_token_kind(app_handle: &AppHandle) -> AppResult<Option<TokenKind>> {
+    Ok(storage::credentials::find(app_handle)?.map(|bundle| bundle.kind))
 }
@@ -1,7 +1,9 @@
 //! This module contains external related to the Todoist API.
 
 pub mod auth;
+pub mod resources;
 pub mod sdk;
+pub mod task;
 
 /// The client ID for the Todoist API.
 ///
@@ -0,0 +1,345 @@
+//! This module keeps the cache of the user's Todoist projects, sections and labels
+//! up to date using the incremental Sync API.
+//!
+//! Listing them serves the cache while it is fresh, so pickers and completions can
+//! query them on every keystroke; otherwise, the changes are synced first.
+
+use crate::external::todoist::auth;
+use crate::external::todoist::sdk::{self, Label, PermissionScope, Project, Section, SyncResponse};
+use crate::ipc::events::CustomEvent;
+use crate::shared::error::AppResult;
+use crate::shared::storage::resources::{self, ResourceCache};
+use anyhow::{bail, ensure};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Listener};
+use tokio::sync::Mutex;
+
+/// How long the cached resources are served without syncing them first.
+const SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Serializes the syncs, so that the same changes are never applied twice.
+static SYNC_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Sets up the synchronization of the resource cache with the user's account.
+///
+/// The resources are synced as soon as the user logs in or grants additional scopes,
+/// and the cache is cleared when they log out, as it belongs to their account.
+pub fn set_up_resource_sync(app_handle: &AppHandle) {
+    log::info!("Setting up resource sync...");
+
+    let owned_app_handle = app_handle.to_owned();
+    app_handle.listen(CustomEvent::Authentication.to_string(), move |event| {
+        let authenticated = serde_json::from_str::<bool>(event.payload()).unwrap_or(false);
+        let app_handle = owned_app_handle.to_owned();
+        tauri::async_runtime::spawn(async move {
+            let result = if authenticated {
+                sync(&app_handle).await.map(|_| ())
+            } else {
+                let _guard = SYNC_LOCK.lock().await;
+                resources::clear(&app_handle)
+            };
+            let _ = result.inspect_err(|e| log::error!("{e:?}"));
+        });
+    });
+
+    let owned_app_handle = app_handle.to_owned();
+    app_handle.listen(CustomEvent::Scopes.to_string(), move |_| {
+        let app_handle = owned_app_handle.to_owned();
+        tauri::async_runtime::spawn(async move {
+            let _ = sync(&app_handle)
+                .await
+                .inspect_err(|e| log::error!("{e:?}"));
+        });
+    });
+}
+
+/// Lists the active projects in the user's order, syncing them first unless the
+/// cache is fresh. With `refresh`, they are always synced.
+pub async fn list_projects(refresh: bool, app_handle: &AppHandle) -> AppResult<Vec<Project>> {
+    Ok(get_cache(refresh, app_handle).await?.projects)
+}
+
+/// Lists the active sections, optionally of a single project, syncing them first
+/// unless the cache is fresh. With `refresh`, they are always synced.
+pub async fn list_sections(
+    project_id: Option<&str>,
+    refresh: bool,
+    app_handle: &AppHandle,
+) -> AppResult<Vec<Section>> {
+    let sections = get_cache(refresh, app_handle)
+        .await?
+        .sections
+        .into_iter()
+        .filter(|section| project_id.is_none_or(|id| section.project_id == id))
+        .collect();
+    Ok(sections)
+}
+
+/// Lists the personal labels in the user's order, syncing them first unless the
+/// cache is fresh. With `refresh`, they are always synced.
+pub async fn list_labels(refresh: bool, app_handle: &AppHandle) -> AppResult<Vec<Label>> {
+    Ok(get_cache(refresh, app_handle).await?.labels)
+}
+
+/// Finds the ID of the project with the given name, ignoring the case, or with the given ID.
+///
+/// If no project matches, the projects are synced again in case it was just created.
+pub async fn find_project_id(name: &str, app_handle: &AppHandle) -> AppResult<String> {
+    let projects = list_projects(false, app_handle).await?;
+    if let Some(project) = find_project(&projects, name)? {
+        return Ok(project.id.to_owned());
+    }
+    let projects = list_projects(true, app_handle).await?;
+    match find_project(&projects, name)? {
+        Some(project) => Ok(project.id.to_owned()),
+        None => bail!("No project is named {name:?}"),
+    }
+}
+
+/// Finds the project with the given name, ignoring the case, or with the given ID,
+/// failing if several projects have the name.
+fn find_project<'a>(projects: &'a [Project], name: &str) -> AppResult<Option<&'a Project>> {
+    let named = projects
+        .iter()
+        .filter(|project| project.name.to_lowercase() == name.to_lowercase())
+        .collect::<Vec<_>>();
+    match named.as_slice() {
+        [] => Ok(projects.iter().find(|project| project.id == name)),
+        [project] => Ok(Some(project)),
+        _ => bail!("Several projects are named {name:?}; pass the ID of the project instead"),
+    }
+}
+
+/// Retrieves the cached resources, syncing them first if they are stale.
+///
+/// If the sync fails, e.g., because the network is down, the stale cache is
+/// served instead, unless nothing has ever been synced.
+async fn get_cache(refresh: bool, app_handle: &AppHandle) -> AppResult<ResourceCache> {
+    let cache = resources::get(app_handle)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    if !refresh && cache.synced_at + SYNC_INTERVAL.as_secs() > now {
+        return Ok(cache);
+    }
+
+    match sync(app_handle).await {
+        Ok(cache) => Ok(cache),
+        Err(e) if cache.synced_at > 0 => {
+            log::warn!("Failed to sync the resources; Serving the cached ones: {e:?}");
+            Ok(cache)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Syncs the changes since the last sync into the cache.
+async fn sync(app_handle: &AppHandle) -> AppResult<ResourceCache> {
+    let _guard = SYNC_LOCK.lock().await;
+    let mut cache = resources::get(app_handle)?;
+
+    ensure!(
+        auth::has_scope(PermissionScope::DataRead, app_handle)?,
+        "Not allowed to read projects and labels; authorize Capturist to read them first"
+    );
+    let sync_token = &cache.sync_token;
+    let response = auth::with_valid_access_token(app_handle, |access_token| async move {
+        sdk::sync_resources(&access_token, sync_token).await
+    })
+    .await
+    .map_err(|e| {
+        if sdk::is_forbidden(&e) {
+            e.context("Not allowed to read projects and labels; authorize Capturist again")
+        } else {
+            e
+        }
+    })?;
+    log::info!(
+        "Synced {} project(s), {} section(s) and {} label(s) (full sync: {}).",
+        response.projects.len(),
+        response.sections.len(),
+        response.labels.len(),
+        response.full_sync
+    );
+
+    apply_changes(&mut cache, response);
+    cache.synced_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    resources::set(&cache, app_handle)?;
+
+    Ok(cache)
+}
+
+/// Applies the resources of a sync response to the cache.
+///
+/// Changed resources replace the cached ones, and the deleted or archived ones are removed.
+fn apply_changes(cache: &mut ResourceCache, response: SyncResponse) {
+    if response.full_sync {
+        cache.projects.clear();
+        cache.sections.clear();
+        cache.labels.clear();
+    }
+    cache.sync_token = response.sync_token;
+
+    for project in response.projects {
+        cache.projects.retain(|cached| cached.id != project.id);
+        if !project.is_deleted && !project.is_archived {
+            cache.projects.push(project);
+        }
+    }
+    cache.projects.sort_by_key(|project| project.child_order);
+
+    for section in response.sections {
+        cache.sections.retain(|cached| cached.id != section.id);
+        if !section.is_deleted && !section.is_archived {
+            cache.sections.push(section);
+        }
+    }
+    cache.sections.sort_by_key(|section| section.section_order);
+
+    for label in response.labels {
+        cache.labels.retain(|cached| cached.id != label.id);
+        if !label.is_deleted {
+            cache.labels.push(label);
+        }
+    }
+    cache.labels.sort_by_key(|label| label.item_order);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sync_response(value: serde_json::Value) -> SyncResponse {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn full_sync_cache() -> ResourceCache {
+        let mut cache = ResourceCache::default();
+        apply_changes(
+            &mut cache,
+            sync_response(json!({
+                "sync_token": "first",
+                "full_sync": true,
+                "projects": [
+                    { "id": "p2", "name": "Work", "parent_id": null, "child_order": 2 },
+                    { "id": "p1", "name": "Inbox", "parent_id": null, "child_order": 1 },
+                ],
+                "sections": [
+                    { "id": "s1", "project_id": "p2", "name": "Doing", "section_order": 1 },
+                ],
+                "labels": [
+                    { "id": "l1", "name": "home", "item_order": 1 },
+                    { "id": "l2", "name": "office", "item_order": 2 },
+                ],
+            })),
+        );
+        cache
+    }
+
+    fn project_names(cache: &ResourceCache) -> Vec<&str> {
+        cache
+            .projects
+            .iter()
+            .map(|project| project.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn applies_a_full_sync_in_order() {
+        let cache = full_sync_cache();
+        assert_eq!(cache.sync_token, "first");
+        assert_eq!(project_names(&cache), ["Inbox", "Work"]);
+        assert_eq!(cache.sections.len(), 1);
+        assert_eq!(cache.labels.len(), 2);
+    }
+
+    #[test]
+    fn replaces_the_cache_on_a_full_sync() {
+        let mut cache = full_sync_cache();
+        apply_changes(
+            &mut cache,
+            sync_response(json!({
+                "sync_token": "second",
+                "full_sync": true,
+                "projects": [
+                    { "id": "p3", "name": "Home", "parent_id": null },
+                ],
+            })),
+        );
+        assert_eq!(cache.sync_token, "second");
+        assert_eq!(project_names(&cache), ["Home"]);
+        assert!(cache.sections.is_empty());
+        assert!(cache.labels.is_empty());
+    }
+
+    #[test]
+    fn applies_incremental_changes() {
+        let mut cache = full_sync_cache();
+        apply_changes(
+            &mut cache,
+            sync_response(json!({
+                "sync_token": "second",
+                "projects": [
+                    { "id": "p2", "name": "Job", "parent_id": null, "child_order": 0 },
+                    { "id": "p3", "name": "Home", "parent_id": null, "child_order": 3 },
+                ],
+                "sections": [
+                    { "id": "s1", "project_id": "p2", "name": "Doing", "is_archived": true },
+                ],
+                "labels": [
+                    { "id": "l1", "name": "home", "is_deleted": true },
+                ],
+            })),
+        );
+        assert_eq!(cache.sync_token, "second");
+        assert_eq!(project_names(&cache), ["Job", "Inbox", "Home"]);
+        assert!(cache.sections.is_empty());
+        assert_eq!(cache.labels.len(), 1);
+        assert_eq!(cache.labels[0].name, "office");
+    }
+
+    #[test]
+    fn finds_a_project_by_name_ignoring_the_case_or_by_id() {
+        let cache = full_sync_cache();
+        let find = |name| {
+            find_project(&cache.projects, name)
+                .unwrap()
+                .map(|p| p.id.as_str())
+        };
+        assert_eq!(find("work"), Some("p2"));
+        assert_eq!(find("INBOX"), Some("p1"));
+        assert_eq!(find("p2"), Some("p2"));
+        assert_eq!(find("Errands"), None);
+    }
+
+    #[test]
+    fn fails_to_find_a_project_by_an_ambiguous_name() {
+        let mut cache = full_sync_cache();
+        apply_changes(
+            &mut cache,
+            sync_response(json!({
+                "sync_token": "second",
+                "projects": [
+                    { "id": "p3", "name": "WORK", "parent_id": "p1" },
+                ],
+            })),
+        );
+        assert!(find_project(&cache.projects, "Work").is_err());
+    }
+
+    #[test]
+    fn removes_deleted_and_archived_projects() {
+        let mut cache = full_sync_cache();
+        apply_changes(
+            &mut cache,
+            sync_response(json!({
+                "sync_token": "second",
+                "projects": [
+                    { "id": "p1", "name": "Inbox", "parent_id": null, "is_deleted": true },
+                    { "id": "p2", "name": "Work", "parent_id": null, "is_archived": true },
+                ],
+            })),
+        );
+        assert!(cache.projects.is_empty());
+        assert_eq!(cache.labels.len(), 2);
+    }
+}
@@ -1,9 +1,11 @@
 use crate::shared::error::AppResult;
-use anyhow::Context;
+use anyhow::{format_err, Context};
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use base64::Engine;
 use rand::RngExt;
-use serde::Deserialize;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 use sha2::{Digest, Sha256};
 use std::fmt;
 use url::Url;
@@ -11,6 +13,21 @@ use url::Url;
 /// The OAuth token endpoint.
 const ACCESS_TOKEN_ENDPOINT: &str = "https://api.todoist.com/oauth/access_token";
 
+/// The OAuth token revocation endpoint (RFC 7009).
+const REVOKE_TOKEN_ENDPOINT: &str = "https://api.todoist.com/api/v1/revoke";
+
+/// The base URL of the Todoist API.
+const API_BASE_URL: &str = "https://api.todoist.com/api/v1";
+
+/// The header used to make task creation requests idempotent.
+const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// The sync token requesting all the resources instead of the changes since a previous sync.
+pub const FULL_SYNC_TOKEN: &str = "*";
+
+/// The base URL of a task in the Todoist web app.
+const TASK_BASE_URL: &str = "https://app.todoist.com/app/task";
+
 /// Represents the response received when exchanging an authorization code (or a
 /// refresh token) for an access token.
 #[derive(Debug, Deserialize)]
@@ -25,30 +42,223 @@ pub struct AccessTokenResponse {
     pub expires_in: u64,
     /// The refresh token. Rotated on every refresh; only present once in any response.
     pub refresh_token: Option<String>,
+    /// The comma-separated scopes granted to the access token, if reported.
+    #[serde(default)]
+    pub scope: Option<String>,
 }
 
 /// Represents the data received in the callback from the Todoist OAuth authorization flow.
-/// This struct is used to deserialize the query parameters from the callback URL.
+/// This enum is used to deserialize the query parameters from the callback URL.
 #[derive(Debug, Deserialize)]
-pub struct AuthCallbackResponse {
-    pub code: String,
-    pub state: String,
+#[serde(untagged)]
+pub enum AuthCallbackResponse {
+    /// The user granted access; the code can be exchanged for an access token.
+    Success { code: String, state: String },
+    /// The authorization failed, e.g., the user denied access.
+    Error(AuthCallbackError),
+}
+
+impl AuthCallbackResponse {
+    /// Returns the CSRF state echoed back by Todoist, if any.
+    pub fn state(&self) -> Option<&str> {
+        match self {
+            AuthCallbackResponse::Success { state, .. } => Some(state),
+            AuthCallbackResponse::Error(error) => error.state.as_deref(),
+        }
+    }
+}
+
+/// Represents an error response of the OAuth authorization flow (RFC 6749, section 4.1.2.1).
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthCallbackError {
+    /// The error code, e.g., `access_denied`.
+    pub error: String,
+    /// The human-readable description of the error, if provided.
+    pub error_description: Option<String>,
+    pub state: Option<String>,
+}
+
+impl fmt::Display for AuthCallbackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.error.as_str() {
+            "access_denied" => write!(f, "Access to your Todoist account was denied"),
+            "invalid_scope" => write!(f, "Todoist did not accept the requested permissions"),
+            "server_error" | "temporarily_unavailable" => {
+                write!(
+                    f,
+                    "Todoist is temporarily unavailable; please try again later"
+                )
+            }
+            error => match &self.error_description {
+                Some(description) => write!(f, "Todoist returned an error: {description}"),
+                None => write!(f, "Todoist returned an error: {error}"),
+            },
+        }
+    }
+}
+
+impl std::error::Error for AuthCallbackError {}
+
+/// Represents the request body of the quick-add endpoint.
+///
+/// The text is parsed by Todoist the same way as in its own quick-add dialog,
+/// so it may contain dates, `#Project`, `@label` and priority keywords.
+#[derive(Debug, Serialize)]
+pub struct QuickAddTaskRequest<'a> {
+    /// The text of the task, including the natural-language keywords.
+    pub text: &'a str,
+    /// The description of the task.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<&'a str>,
+    /// Whether the default reminder should be added when a due time is detected.
+    pub auto_reminder: bool,
+}
+
+/// Represents the request body of the add-task endpoint.
+#[derive(Debug, Serialize)]
+pub struct AddTaskRequest<'a> {
+    /// The content of the task.
+    pub content: &'a str,
+    /// The description of the task.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<&'a str>,
+    /// The ID of the project to add the task to. Defaults to the inbox.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<&'a str>,
+    /// The names of the labels to attach to the task.
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    pub labels: &'a [String],
+    /// The priority of the task, from 1 (normal) to 4 (urgent).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u8>,
+    /// The due date of the task in natural language, e.g. "tomorrow at 5pm".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_string: Option<&'a str>,
+}
+
+/// Represents a task as returned by the Todoist API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Task {
+    pub id: String,
+    pub content: String,
+    #[serde(default)]
+    pub description: String,
+    pub project_id: String,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    pub priority: u8,
+    pub due: Option<Due>,
+    /// The URL of the task in the Todoist web app.
+    #[serde(default)]
+    pub url: String,
+}
+
+/// Represents the due date of a task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Due {
+    /// The due date in `YYYY-MM-DD` format, or a full date-time when a time is set.
+    pub date: String,
+    /// The human-readable representation of the due date.
+    pub string: String,
+    pub is_recurring: bool,
+}
+
+/// Represents a project as returned by the Todoist API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    pub parent_id: Option<String>,
+    #[serde(default)]
+    pub color: String,
+    /// Whether this is the user's inbox, which tasks are added to by default.
+    #[serde(default)]
+    pub inbox_project: bool,
+    #[serde(default)]
+    pub child_order: i64,
+    #[serde(default)]
+    pub is_archived: bool,
+    /// Whether the project was deleted. Only reported by incremental syncs.
+    #[serde(default)]
+    pub is_deleted: bool,
+}
+
+/// Represents a section of a project as returned by the Todoist API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Section {
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub section_order: i64,
+    #[serde(default)]
+    pub is_archived: bool,
+    /// Whether the section was deleted. Only reported by incremental syncs.
+    #[serde(default)]
+    pub is_deleted: bool,
+}
+
+/// Represents a personal label as returned by the Todoist API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Label {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub color: String,
+    #[serde(default)]
+    pub item_order: i64,
+    #[serde(default)]
+    pub is_favorite: bool,
+    /// Whether the label was deleted. Only reported by incremental syncs.
+    #[serde(default)]
+    pub is_deleted: bool,
+}
+
+/// Represents the response of the Sync API.
+///
+/// With a sync token from a previous response, only the resources changed since
+/// then are returned; otherwise, `full_sync` is set and all of them are returned.
+#[derive(Debug, Deserialize)]
+pub struct SyncResponse {
+    /// The token to pass to the next sync to receive only the changes.
+    pub sync_token: String,
+    /// Whether all the resources were returned instead of the changes only.
+    #[serde(default)]
+    pub full_sync: bool,
+    #[serde(default)]
+    pub projects: Vec<Project>,
+    #[serde(default)]
+    pub sections: Vec<Section>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+}
+
+/// Represents the user the access token belongs to.
+#[derive(Debug, Clone, Deserialize)]
+pub struct User {
+    pub id: String,
 }
 
 /// Represents the permission scopes for the Todoist API.
-#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum PermissionScope {
     /// Grants permission to add new tasks (the application cannot read or modify any existing data).
+    #[serde(rename = "task:add")]
     TaskAdd,
     /// Grants read-only access to application data, including tasks, projects, labels, and filters.
+    #[serde(rename = "data:read")]
     DataRead,
     /// Grants read and write access to application data, including tasks, projects, labels, and filters. This scope includes `task:add` and `data:read` scopes.
+    #[serde(rename = "data:read_write")]
     DataReadWrite,
     /// Grants permission to delete application data, including tasks, labels, and filters.
+    #[serde(rename = "data:delete")]
     DataDelete,
     /// Grants permission to delete projects.
+    #[serde(rename = "project:delete")]
     ProjectDelete,
     /// Grants permission to list backups bypassing MFA requirements.
+    #[serde(rename = "backups:read")]
     BackupsRead,
 }
 
@@ -65,6 +275,41 @@ impl fmt::Display for PermissionScope {
     }
 }
 
+impl TryFrom<&str> for PermissionScope {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> AppResult<Self> {
+        match value {
+            "task:add" => Ok(Self::TaskAdd),
+            "data:read" => Ok(Self::DataRead),
+            "data:read_write" => Ok(Self::DataReadWrite),
+            "data:delete" => Ok(Self::DataDelete),
+            "project:delete" => Ok(Self::ProjectDelete),
+            "backups:read" => Ok(Self::BackupsRead),
+            _ => Err(format_err!("Unknown permission scope: {}", value)),
+        }
+    }
+}
+
+impl PermissionScope {
+    /// Checks whether this scope grants the permissions of the other one as well.
+    pub fn includes(&self, other: PermissionScope) -> bool {
+        *self == other
+            || (*self == PermissionScope::DataReadWrite
+                && matches!(other, PermissionScope::TaskAdd | PermissionScope::DataRead))
+    }
+}
+
+/// Parses comma-separated permission scopes, as reported by Todoist.
+pub fn parse_scopes(scopes: &str) -> AppResult<Vec<PermissionScope>> {
+    scopes
+        .split(',')
+        .map(str::trim)
+        .filter(|scope| !scope.is_empty())
+        .map(PermissionScope::try_from)
+        .collect()
+}
+
 /// Generates a secure, random 24-character alphanumeric string to be used
 /// as the `state` parameter in an OAuth2 flow.
 ///
@@ -98,12 +343,15 @@ pub fn get_pkce_challenge(verifier: &str) -> String {
 
 /// Constructs the full Todoist authorization URL.
 ///
+/// If no `redirect_uri` is given, Todoist redirects to the one registered for the client.
+///
 /// This is the equivalent of `getAuthorizationUrl`.
 pub fn get_authorization_url(
     client_id: &str,
     scopes: &[PermissionScope],
     state: &str,
     code_challenge: &str,
+    redirect_uri: Option<&str>,
 ) -> AppResult<Url> {
     let mut url = Url::parse("https://todoist.com/oauth/authorize")
         .context("Failed to parse Todoist authorization base URL")?;
@@ -118,25 +366,35 @@ pub fn get_authorization_url(
         .append_pair("state", state)
         .append_pair("code_challenge", code_challenge)
         .append_pair("code_challenge_method", "S256");
+    if let Some(redirect_uri) = redirect_uri {
+        url.query_pairs_mut()
+            .append_pair("redirect_uri", redirect_uri);
+    }
     Ok(url)
 }
 
 /// Exchanges an authorization code for an access token.
 ///
 /// This is the equivalent of `getAuthToken`, using a PKCE public client so no
-/// client secret is required.
+/// client secret is required. The `redirect_uri` must match the one used in the
+/// authorization URL, if any.
 pub async fn get_auth_token(
     client_id: &str,
     code: &str,
     code_verifier: &str,
+    redirect_uri: Option<&str>,
 ) -> AppResult<AccessTokenResponse> {
+    let mut form = vec![
+        ("client_id", client_id),
+        ("code", code),
+        ("code_verifier", code_verifier),
+    ];
+    if let Some(redirect_uri) = redirect_uri {
+        form.push(("redirect_uri", redirect_uri));
+    }
     let response = reqwest::Client::new()
         .post(ACCESS_TOKEN_ENDPOINT)
-        .form(&[
-            ("client_id", client_id),
-            ("code", code),
-            ("code_verifier", code_verifier),
-        ])
+        .form(&form)
         .send()
         .await?
         .json::<AccessTokenResponse>()
@@ -161,7 +419,223 @@ pub async fn refresh_access_token(
         ])
         .send()
         .await?
+        .error_for_status()?
         .json::<AccessTokenResponse>()
         .await?;
     Ok(response)
 }
+
+/// Revokes an access or refresh token, so it can no longer be used by anyone.
+///
+/// The `token_type_hint` is either `access_token` or `refresh_token`.
+pub async fn revoke_token(client_id: &str, token: &str, token_type_hint: &str) -> AppResult<()> {
+    reqwest::Client::new()
+        .post(REVOKE_TOKEN_ENDPOINT)
+        .form(&[
+            ("client_id", client_id),
+            ("token", token),
+            ("token_type_hint", token_type_hint),
+        ])
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Adds a task using Todoist's natural-language quick-add parser.
+///
+/// Todoist discards requests with an already seen `request_id`, so retrying
+/// with the same ID never creates a duplicate task.
+pub async fn quick_add_task(
+    access_token: &str,
+    request: &QuickAddTaskRequest<'_>,
+    request_id: &str,
+) -> AppResult<Task> {
+    let task = reqwest::Client::new()
+        .post(format!("{API_BASE_URL}/tasks/quick"))
+        .bearer_auth(access_token)
+        .header(REQUEST_ID_HEADER, request_id)
+        .json(request)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Task>()
+        .await?;
+    Ok(with_task_url(task))
+}
+
+/// Adds a task with explicitly provided attributes.
+///
+/// Todoist discards requests with an already seen `request_id`, so retrying
+/// with the same ID never creates a duplicate task.
+pub async fn add_task(
+    access_token: &str,
+    request: &AddTaskRequest<'_>,
+    request_id: &str,
+) -> AppResult<Task> {
+    let task = reqwest::Client::new()
+        .post(format!("{API_BASE_URL}/tasks"))
+        .bearer_auth(access_token)
+        .header(REQUEST_ID_HEADER, request_id)
+        .json(request)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Task>()
+        .await?;
+    Ok(with_task_url(task))
+}
+
+/// Deletes a task, which requires the `data:delete` scope.
+pub async fn delete_task(access_token: &str, task_id: &str) -> AppResult<()> {
+    reqwest::Client::new()
+        .delete(format!("{API_BASE_URL}/tasks/{task_id}"))
+        .bearer_auth(access_token)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Moves a task to another project.
+pub async fn move_task(access_token: &str, task_id: &str, project_id: &str) -> AppResult<()> {
+    reqwest::Client::new()
+        .post(format!("{API_BASE_URL}/tasks/{task_id}/move"))
+        .bearer_auth(access_token)
+        .json(&json!({ "project_id": project_id }))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Retrieves the projects, sections and labels changed since the sync that returned
+/// `sync_token`, or all of them with [`FULL_SYNC_TOKEN`].
+///
+/// This requires the `data:read` scope.
+pub async fn sync_resources(access_token: &str, sync_token: &str) -> AppResult<SyncResponse> {
+    let resource_types = serde_json::to_string(&["projects", "sections", "labels"])?;
+    let response = reqwest::Client::new()
+        .post(format!("{API_BASE_URL}/sync"))
+        .bearer_auth(access_token)
+        .form(&[
+            ("sync_token", sync_token),
+            ("resource_types", &resource_types),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<SyncResponse>()
+        .await?;
+    Ok(response)
+}
+
+/// Retrieves the user the access token belongs to.
+///
+/// This is also a cheap way to check whether an access token is valid.
+pub async fn get_user(access_token: &str) -> AppResult<User> {
+    let user = reqwest::Client::new()
+        .get(format!("{API_BASE_URL}/user"))
+        .bearer_auth(access_token)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<User>()
+        .await?;
+    Ok(user)
+}
+
+/// Checks whether the given error is an API response with `401 Unauthorized`,
+/// i.e. the access token has expired or has been revoked.
+pub fn is_unauthorized(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<reqwest::Error>())
+        .and_then(reqwest::Error::status)
+        .is_some_and(|status| status == StatusCode::UNAUTHORIZED)
+}
+
+/// Checks whether the given error is an API response with `403 Forbidden`,
+/// e.g., the access token lacks the scope required by the request.
+pub fn is_forbidden(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<reqwest::Error>())
+        .and_then(reqwest::Error::status)
+        .is_some_and(|status| status == StatusCode::FORBIDDEN)
+}
+
+/// Checks whether the given error is likely to go away by retrying later,
+/// e.g., the network is down or the API is temporarily unavailable.
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<reqwest::Error>())
+        .is_some_and(|e| match e.status() {
+            Some(status) => {
+                status.is_server_error()
+                    || status == StatusCode::TOO_MANY_REQUESTS
+                    || status == StatusCode::REQUEST_TIMEOUT
+            }
+            None => e.is_connect() || e.is_timeout() || e.is_request(),
+        })
+}
+
+/// Checks whether the given error is an API response rejecting the request itself,
+/// i.e. retrying the same request can never succeed.
+pub fn is_rejected(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<reqwest::Error>())
+        .and_then(reqwest::Error::status)
+        .is_some_and(|status| {
+            status.is_client_error()
+                && status != StatusCode::UNAUTHORIZED
+                && status != StatusCode::REQUEST_TIMEOUT
+                && status != StatusCode::TOO_MANY_REQUESTS
+        })
+}
+
+/// Fills in the web app URL of a task, which the API does not always include.
+fn with_task_url(mut task: Task) -> Task {
+    if task.url.is_empty() {
+        task.url = format!("{TASK_BASE_URL}/{}", task.id);
+    }
+    task
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_comma_separated_scopes() {
+        assert_eq!(
+            parse_scopes("task:add, data:read_write,").unwrap(),
+            [PermissionScope::TaskAdd, PermissionScope::DataReadWrite]
+        );
+        assert!(parse_scopes("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn fails_to_parse_unknown_scopes() {
+        assert!(parse_scopes("task:add,data:everything").is_err());
+    }
+
+    #[test]
+    fn includes_the_scopes_implied_by_read_write() {
+        let read_write = PermissionScope::DataReadWrite;
+        assert!(read_write.includes(PermissionScope::DataReadWrite));
+        assert!(read_write.includes(PermissionScope::TaskAdd));
+        assert!(read_write.includes(PermissionScope::DataRead));
+        assert!(!read_write.includes(PermissionScope::DataDelete));
+    }
+
+    #[test]
+    fn includes_only_itself_otherwise() {
+        assert!(PermissionScope::TaskAdd.includes(PermissionScope::TaskAdd));
+        assert!(!PermissionScope::TaskAdd.includes(PermissionScope::DataRead));
+        assert!(!PermissionScope::DataRead.includes(PermissionScope::DataReadWrite));
+        assert!(!PermissionScope::DataDelete.includes(PermissionScope::ProjectDelete));
+    }
+}
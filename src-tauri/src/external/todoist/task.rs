@@ -0,0 +1,421 @@
+//! This module creates Todoist tasks on behalf of the frontend and the CLI,
+//! so the access token never has to leave the backend.
+
+use crate::external::todoist::auth;
+use crate::external::todoist::sdk::{
+    self, AddTaskRequest, PermissionScope, QuickAddTaskRequest, Task,
+};
+use crate::ipc::events::CustomEvent;
+use crate::shared::error::AppResult;
+use crate::shared::state::AppState;
+use crate::shared::storage::history::{self, CaptureStatus, HistoryEntry};
+use crate::shared::storage::{outbox, resources, secret_store, settings};
+use anyhow::{ensure, Context};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Listener, Manager};
+use tauri_plugin_opener::OpenerExt;
+
+/// The delay before retrying the outbox delivery after the first failure.
+const OUTBOX_MIN_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// The upper bound of the exponential backoff between outbox delivery attempts.
+const OUTBOX_MAX_RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// Represents a task to be created.
+///
+/// If only the content and the description are set, the content is parsed with
+/// Todoist's quick-add syntax (dates, `#Project`, `@label`, `p1`...). Otherwise,
+/// the task is created as is with the given attributes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewTask {
+    /// The content of the task.
+    pub content: String,
+    /// The description of the task.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The ID of the project to add the task to.
+    #[serde(default)]
+    pub project_id: Option<String>,
+    /// The names of the labels to attach to the task.
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// The priority of the task, from 1 (normal) to 4 (urgent).
+    #[serde(default)]
+    pub priority: Option<u8>,
+    /// The due date of the task in natural language, e.g. "tomorrow at 5pm".
+    #[serde(default)]
+    pub due: Option<String>,
+}
+
+impl NewTask {
+    /// Checks whether any attribute other than the content and the description is set.
+    fn has_explicit_attributes(&self) -> bool {
+        self.project_id.is_some()
+            || !self.labels.is_empty()
+            || self.priority.is_some()
+            || self.due.is_some()
+    }
+}
+
+/// Represents the outcome of submitting a task.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TaskSubmission {
+    /// The task was created in Todoist.
+    Created { task: Task },
+    /// The task could not be sent and is waiting in the outbox.
+    Queued { entry_id: String, reason: String },
+}
+
+/// Submits a new task to Todoist.
+///
+/// If the task cannot be sent due to a transient error, e.g., the network is down or
+/// the keyring is locked, it is stored in the outbox and delivered later, so it is never lost. If it is
+/// rejected, it is kept in the outbox as failed, until the user retries or discards it.
+pub async fn submit_task(task: NewTask, app_handle: &AppHandle) -> AppResult<TaskSubmission> {
+    validate(&task)?;
+    let request_id = outbox::new_entry_id();
+    match add_task(&task, &request_id, app_handle).await {
+        Ok(created_task) => {
+            app_handle.state::<AppState>().outbox_notify.notify_one();
+            record_history(
+                &request_id,
+                &task,
+                Ok(&created_task),
+                CaptureStatus::Sent,
+                app_handle,
+            );
+            Ok(TaskSubmission::Created { task: created_task })
+        }
+        Err(e) if sdk::is_transient(&e) || secret_store::is_keyring_locked(&e) => {
+            log::warn!("Failed to add task; Queuing it in the outbox: {e:?}");
+            let entry = outbox::push(&request_id, &task, &e, false, app_handle)?;
+            emit_outbox_summary(app_handle)?;
+            record_history(
+                &request_id,
+                &task,
+                Err(&e),
+                CaptureStatus::Queued,
+                app_handle,
+            );
+            Ok(TaskSubmission::Queued {
+                entry_id: entry.id,
+                reason: e.to_string(),
+            })
+        }
+        Err(e) => {
+            log::error!("Failed to add task; Keeping it in the outbox: {e:?}");
+            outbox::push(&request_id, &task, &e, true, app_handle)?;
+            emit_outbox_summary(app_handle)?;
+            record_history(
+                &request_id,
+                &task,
+                Err(&e),
+                CaptureStatus::Failed,
+                app_handle,
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Retries the delivery of an outbox entry, e.g., one that failed permanently.
+pub fn retry_outbox_entry(entry_id: &str, app_handle: &AppHandle) -> AppResult<()> {
+    ensure!(
+        outbox::mark_pending(entry_id, app_handle)?,
+        "The outbox entry does not exist"
+    );
+    emit_outbox_summary(app_handle)?;
+    app_handle.state::<AppState>().outbox_notify.notify_one();
+    Ok(())
+}
+
+/// Discards an outbox entry, so the task is never delivered.
+pub fn discard_outbox_entry(entry_id: &str, app_handle: &AppHandle) -> AppResult<()> {
+    log::info!("Discarding outbox entry {entry_id}...");
+    outbox::remove(entry_id, app_handle)?;
+    emit_outbox_summary(app_handle)
+}
+
+/// Submits the task of a history entry again, as a new capture.
+pub async fn recapture(entry_id: &str, app_handle: &AppHandle) -> AppResult<TaskSubmission> {
+    let entry = find_history_entry(entry_id, app_handle)?;
+    submit_task(entry.task, app_handle).await
+}
+
+/// Opens the task of a history entry in Todoist.
+pub fn open_in_todoist(entry_id: &str, app_handle: &AppHandle) -> AppResult<()> {
+    let entry = find_history_entry(entry_id, app_handle)?;
+    ensure!(
+        entry.status == CaptureStatus::Sent,
+        "The task does not exist in Todoist"
+    );
+    let url = entry.task_url.context("Missing task URL")?;
+    app_handle.opener().open_url(url, None::<&str>)?;
+    Ok(())
+}
+
+/// Sets up the background delivery of the tasks waiting in the outbox.
+///
+/// The outbox is flushed on startup and then retried with an exponential backoff
+/// until it is empty. It is woken up early whenever a task is sent successfully
+/// or the user logs in.
+pub fn set_up_outbox_delivery(app_handle: &AppHandle) {
+    log::info!("Setting up outbox delivery...");
+
+    let owned_app_handle = app_handle.to_owned();
+    app_handle.listen(CustomEvent::Authentication.to_string(), move |event| {
+        if serde_json::from_str::<bool>(event.payload()).unwrap_or(false) {
+            owned_app_handle
+                .state::<AppState>()
+                .outbox_notify
+                .notify_one();
+        }
+    });
+
+    let app_handle = app_handle.to_owned();
+    tauri::async_runtime::spawn(async move {
+        let mut retry_delay = OUTBOX_MIN_RETRY_DELAY;
+        loop {
+            let delivered = deliver_outbox(&app_handle)
+                .await
+                .inspect_err(|e| log::error!("{e:?}"))
+                .unwrap_or(false);
+            let state = app_handle.state::<AppState>();
+            if delivered {
+                retry_delay = OUTBOX_MIN_RETRY_DELAY;
+                state.outbox_notify.notified().await;
+            } else {
+                log::info!("Retrying the outbox delivery in {retry_delay:?}...");
+                tokio::select! {
+                    _ = tokio::time::sleep(retry_delay) => {}
+                    _ = state.outbox_notify.notified() => {}
+                }
+                retry_delay = (retry_delay * 2).min(OUTBOX_MAX_RETRY_DELAY);
+            }
+        }
+    });
+}
+
+/// Emits the current number of pending and failed outbox entries.
+pub fn emit_outbox_summary(app_handle: &AppHandle) -> AppResult<()> {
+    app_handle.emit(
+        &CustomEvent::Outbox.to_string(),
+        outbox::summarize(app_handle)?,
+    )?;
+    Ok(())
+}
+
+/// Deletes the most recently captured task that can still be undone.
+///
+/// Returns the ID of the deleted task.
+pub async fn undo_last_task(app_handle: &AppHandle) -> AppResult<String> {
+    let task_id = history::list_undoable(app_handle)?
+        .into_iter()
+        .find_map(|entry| entry.task_id)
+        .context("There is no task to undo")?;
+    undo_task(&task_id, app_handle).await?;
+    Ok(task_id)
+}
+
+/// Deletes a task created by the application, e.g., because it was captured by mistake.
+///
+/// This requires the undo setting to be enabled, the user to have granted the
+/// `data:delete` scope, and the task to have been captured interactively within
+/// the undo period, as recorded in the history.
+pub async fn undo_task(task_id: &str, app_handle: &AppHandle) -> AppResult<()> {
+    ensure!(
+        settings::get(app_handle)?.undo,
+        "Undoing tasks is not enabled in the settings"
+    );
+    ensure!(
+        history::list_undoable(app_handle)?
+            .iter()
+            .any(|entry| entry.task_id.as_deref() == Some(task_id)),
+        "The task can no longer be undone"
+    );
+    ensure!(
+        auth::has_scope(PermissionScope::DataDelete, app_handle)?,
+        "Not allowed to delete tasks; authorize Capturist to delete them in the settings"
+    );
+
+    auth::with_valid_access_token(app_handle, |access_token| async move {
+        sdk::delete_task(&access_token, task_id).await
+    })
+    .await
+    .map_err(|e| {
+        if sdk::is_forbidden(&e) {
+            e.context("Not allowed to delete tasks; authorize Capturist again in the settings")
+        } else {
+            e
+        }
+    })?;
+    log::info!("Task {task_id} undone.");
+    history::mark_undone(task_id, app_handle)?;
+
+    Ok(())
+}
+
+/// Tries to deliver every pending outbox entry in the order they were queued.
+///
+/// Entries rejected by Todoist are kept but no longer retried. Returns `false`
+/// if the delivery was interrupted by any other error and should be retried later.
+pub async fn deliver_outbox(app_handle: &AppHandle) -> AppResult<bool> {
+    if !*app_handle.state::<AppState>().authenticated.lock().unwrap() {
+        return Ok(true);
+    }
+
+    let entries = outbox::list(app_handle)?
+        .into_iter()
+        .filter(|entry| !entry.failed)
+        .collect::<Vec<_>>();
+    if entries.is_empty() {
+        return Ok(true);
+    }
+
+    log::info!("Delivering {} task(s) from the outbox...", entries.len());
+    let mut delivered = true;
+    for entry in entries {
+        match add_task(&entry.task, &entry.id, app_handle).await {
+            Ok(created_task) => {
+                outbox::remove(&entry.id, app_handle)?;
+                let result = Ok(&created_task);
+                record_history(
+                    &entry.id,
+                    &entry.task,
+                    result,
+                    CaptureStatus::Sent,
+                    app_handle,
+                );
+            }
+            Err(e) if sdk::is_rejected(&e) => {
+                log::error!("Outbox entry {} was rejected: {e:?}", entry.id);
+                outbox::record_failure(&entry.id, &e, true, app_handle)?;
+                record_history(
+                    &entry.id,
+                    &entry.task,
+                    Err(&e),
+                    CaptureStatus::Failed,
+                    app_handle,
+                );
+            }
+            Err(e) => {
+                log::warn!("Outbox delivery interrupted: {e:?}");
+                outbox::record_failure(&entry.id, &e, false, app_handle)?;
+                delivered = false;
+                break;
+            }
+        }
+    }
+    emit_outbox_summary(app_handle)?;
+
+    Ok(delivered)
+}
+
+/// Records a capture in the history.
+///
+/// Failures are only logged, as the history must never prevent a task from being captured.
+fn record_history(
+    id: &str,
+    task: &NewTask,
+    result: Result<&Task, &anyhow::Error>,
+    status: CaptureStatus,
+    app_handle: &AppHandle,
+) {
+    let _ =
+        history::record(id, task, result, status, app_handle).inspect_err(|e| log::error!("{e:?}"));
+}
+
+/// Retrieves a history entry, failing if it does not exist (anymore).
+fn find_history_entry(entry_id: &str, app_handle: &AppHandle) -> AppResult<HistoryEntry> {
+    history::find(entry_id, app_handle)?.context("The history entry does not exist")
+}
+
+/// Returns the ID of the default project, if it is set and still exists.
+fn get_default_project_id(app_handle: &AppHandle) -> AppResult<Option<String>> {
+    let Some(project_id) = settings::get(app_handle)?.default_project_id else {
+        return Ok(None);
+    };
+    let projects = resources::get(app_handle)?.projects;
+    // The cache is empty until the first sync, so the project cannot be checked yet.
+    if !projects.is_empty() && !projects.iter().any(|project| project.id == project_id) {
+        log::warn!("The default project {project_id} no longer exists; Using the inbox instead.");
+        return Ok(None);
+    }
+    Ok(Some(project_id))
+}
+
+/// Checks whether a task names its project, either explicitly or with a `#Project`
+/// token in its quick-add text.
+fn names_project(task: &NewTask) -> bool {
+    task.project_id.is_some()
+        || task
+            .content
+            .split_whitespace()
+            .any(|word| word.len() > 1 && word.starts_with('#'))
+}
+
+/// Checks whether a task can be sent at all, before it is kept anywhere.
+fn validate(task: &NewTask) -> AppResult<()> {
+    ensure!(!task.content.trim().is_empty(), "Task content must not be empty");
+    ensure!(
+        task.priority.is_none_or(|priority| (1..=4).contains(&priority)),
+        "Task priority must be between 1 and 4"
+    );
+    Ok(())
+}
+
+/// Adds a new task to Todoist, refreshing the access token if needed.
+///
+/// Requests with the same `request_id` create the task only once.
+async fn add_task(task: &NewTask, request_id: &str, app_handle: &AppHandle) -> AppResult<Task> {
+    validate(task)?;
+
+    let description = task.description.as_deref().filter(|d| !d.is_empty());
+    let default_project_id = get_default_project_id(app_handle)?;
+    let mut created_task = if task.has_explicit_attributes() {
+        let request = &AddTaskRequest {
+            content: &task.content,
+            description,
+            project_id: task.project_id.as_deref().or(default_project_id.as_deref()),
+            labels: &task.labels,
+            priority: task.priority,
+            due_string: task.due.as_deref(),
+        };
+        auth::with_valid_access_token(app_handle, |access_token| async move {
+            sdk::add_task(&access_token, request, request_id).await
+        })
+        .await?
+    } else {
+        let request = &QuickAddTaskRequest {
+            text: &task.content,
+            note: description,
+            auto_reminder: true,
+        };
+        auth::with_valid_access_token(app_handle, |access_token| async move {
+            sdk::quick_add_task(&access_token, request, request_id).await
+        })
+        .await?
+    };
+    log::info!("Task {} added.", created_task.id);
+
+    // The quick-add endpoint cannot target a project, so the task is moved afterward
+    // unless its text names one. A failure leaves the task in the inbox.
+    if let Some(project_id) = default_project_id.filter(|_| !names_project(task)) {
+        if created_task.project_id != project_id {
+            let task_id = &created_task.id;
+            let project_id = &project_id;
+            match auth::with_valid_access_token(app_handle, |access_token| async move {
+                sdk::move_task(&access_token, task_id, project_id).await
+            })
+            .await
+            {
+                Ok(()) => created_task.project_id = project_id.to_owned(),
+                Err(e) => log::warn!("Failed to move task {task_id} to the default project: {e:?}"),
+            }
+        }
+    }
+
+    Ok(created_task)
+}
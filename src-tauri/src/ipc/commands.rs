@@ -1,11 +1,18 @@
-use crate::desktop::shortcut;
-use crate::external::todoist::auth;
+use crate::desktop::keybinding::{self, DesktopEnvironment, InstalledKeybinding};
+use crate::desktop::notification::{self, NotificationAction, NotificationContext};
+use crate::desktop::secret_service;
+use crate::desktop::shortcut::{self, ShortcutBinding, ShortcutStatus};
+use crate::external::todoist::sdk::{Label, PermissionScope, Project, Section};
+use crate::external::todoist::task::{self, NewTask, TaskSubmission};
+use crate::external::todoist::{auth, resources};
 use crate::shared::environment;
 use crate::shared::error::AppSerializableResult;
-use crate::shared::metadata::APP_ID;
 use crate::shared::state::AppState;
-use anyhow::{format_err, Context};
-use std::process::Command;
+use crate::shared::storage::history::{self, HistoryEntry};
+use crate::shared::storage::outbox::{self, OutboxEntry, OutboxSummary};
+use crate::shared::storage::secret_store::{self, KeyringState};
+use crate::shared::storage::settings::{self, Settings};
+use std::ops::Not;
 use tauri::{AppHandle, State};
 
 /// Checks if the application is running in debug mode.
@@ -47,72 +54,300 @@ pub async fn start_authentication(
     auth::start_authentication(&app_handle, &app_state).map_err(Into::into)
 }
 
-/// Returns a valid Todoist access token, refreshing it first if it is about to
-/// expire.
+/// Cancels the Todoist authentication flow in progress, if any.
 #[tauri::command]
-pub async fn get_todoist_access_token(app_handle: AppHandle) -> AppSerializableResult<String> {
-    auth::get_valid_access_token(&app_handle)
+pub fn cancel_authentication(app_handle: AppHandle) -> AppSerializableResult<()> {
+    auth::cancel_authentication(&app_handle).map_err(Into::into)
+}
+
+/// Re-runs the Todoist authentication flow to grant additional permission scopes,
+/// e.g., when a feature needing them is enabled.
+#[tauri::command]
+pub async fn request_additional_scopes(
+    app_handle: AppHandle,
+    app_state: State<'_, AppState>,
+    scopes: Vec<PermissionScope>,
+) -> AppSerializableResult<()> {
+    auth::request_additional_scopes(&scopes, &app_handle, &app_state).map_err(Into::into)
+}
+
+/// Checks whether the current session has been granted a permission scope.
+#[tauri::command]
+pub fn has_scope(app_handle: AppHandle, scope: PermissionScope) -> AppSerializableResult<bool> {
+    auth::has_scope(scope, &app_handle).map_err(Into::into)
+}
+
+/// Logs in with a Todoist personal API token instead of the OAuth flow.
+#[tauri::command]
+pub async fn log_in_with_personal_token(
+    app_handle: AppHandle,
+    token: String,
+) -> AppSerializableResult<()> {
+    auth::log_in_with_personal_token(&token, &app_handle)
         .await
         .map_err(Into::into)
 }
 
-/// Forces a refresh of the Todoist access token. Called by the frontend when an
-/// API request fails with 401 (expired access token).
+/// Logs out after revoking the Todoist tokens. With `everywhere`, all the data
+/// cached by the application is cleared as well.
 #[tauri::command]
-pub async fn refresh_todoist_access_token(app_handle: AppHandle) -> AppSerializableResult<()> {
-    auth::refresh_stored_token(&app_handle)
+pub async fn log_out(app_handle: AppHandle, everywhere: bool) -> AppSerializableResult<()> {
+    auth::revoke_and_log_out(everywhere, &app_handle)
         .await
         .map_err(Into::into)
 }
 
-/// Returns the accelerator string for the global shortcut.
+/// Adds a new task to Todoist, or queues it in the outbox if it cannot be sent right now.
+///
+/// The request is made by the backend, so the access token never reaches the webview.
+#[tauri::command]
+pub async fn add_task(
+    app_handle: AppHandle,
+    task: NewTask,
+) -> AppSerializableResult<TaskSubmission> {
+    task::submit_task(task, &app_handle)
+        .await
+        .map_err(Into::into)
+}
+
+/// Deletes the most recently captured task, which requires the undo setting to be enabled.
+///
+/// Returns the ID of the deleted task.
+#[tauri::command]
+pub async fn undo_last_task(app_handle: AppHandle) -> AppSerializableResult<String> {
+    task::undo_last_task(&app_handle).await.map_err(Into::into)
+}
+
+/// Returns the number of tasks waiting in the outbox.
+#[tauri::command]
+pub fn get_outbox_summary(app_handle: AppHandle) -> AppSerializableResult<OutboxSummary> {
+    outbox::summarize(&app_handle).map_err(Into::into)
+}
+
+/// Returns the tasks waiting in the outbox, including the ones that failed permanently,
+/// in the order they were queued.
+#[tauri::command]
+pub fn list_outbox(app_handle: AppHandle) -> AppSerializableResult<Vec<OutboxEntry>> {
+    outbox::list(&app_handle).map_err(Into::into)
+}
+
+/// Retries the delivery of an outbox entry that failed permanently.
 #[tauri::command]
-pub fn get_global_shortcut() -> String {
-    shortcut::get_global_shortcut_accelerator()
+pub fn retry_outbox_entry(app_handle: AppHandle, id: String) -> AppSerializableResult<()> {
+    task::retry_outbox_entry(&id, &app_handle).map_err(Into::into)
 }
 
+/// Discards an outbox entry, so its task is never added.
 #[tauri::command]
-pub fn get_quick_add_command() -> String {
-    let quick_add_command_argument = "--quick-add";
-    let quick_add_command_base = if environment::is_running_as_appimage() {
-        "<AppImage path>"
-    } else if environment::is_running_as_flatpak() {
-        "flatpak run me.ariyadey.capturist"
-    } else {
-        "capturist"
-    };
-    format!("{quick_add_command_base} {quick_add_command_argument}")
+pub fn discard_outbox_entry(app_handle: AppHandle, id: String) -> AppSerializableResult<()> {
+    task::discard_outbox_entry(&id, &app_handle).map_err(Into::into)
 }
 
-/// Sends a desktop notification using `notify-send`.
+/// Returns the user's active Todoist projects, from the cache if it is fresh.
 ///
-/// This command is only available on Linux systems with `notify-send` installed.
-/// It sends a notification with a specified title and body, and sets the application name.
+/// With `refresh`, the cache is synced first regardless of its age.
+#[tauri::command]
+pub async fn list_projects(
+    app_handle: AppHandle,
+    refresh: Option<bool>,
+) -> AppSerializableResult<Vec<Project>> {
+    resources::list_projects(refresh.unwrap_or(false), &app_handle)
+        .await
+        .map_err(Into::into)
+}
+
+/// Returns the user's active Todoist sections, optionally of a single project,
+/// from the cache if it is fresh.
+#[tauri::command]
+pub async fn list_sections(
+    app_handle: AppHandle,
+    project_id: Option<String>,
+    refresh: Option<bool>,
+) -> AppSerializableResult<Vec<Section>> {
+    resources::list_sections(project_id.as_deref(), refresh.unwrap_or(false), &app_handle)
+        .await
+        .map_err(Into::into)
+}
+
+/// Returns the user's personal Todoist labels, from the cache if it is fresh.
 ///
-/// Note: The Tauri notifications plugin is not used due to an issue where it
-/// does not open notifications on newer Gnome versions
+/// With `refresh`, the cache is synced first regardless of its age.
+#[tauri::command]
+pub async fn list_labels(
+    app_handle: AppHandle,
+    refresh: Option<bool>,
+) -> AppSerializableResult<Vec<Label>> {
+    resources::list_labels(refresh.unwrap_or(false), &app_handle)
+        .await
+        .map_err(Into::into)
+}
+
+/// Returns the history of captured tasks, most recent first.
+#[tauri::command]
+pub fn list_history(app_handle: AppHandle) -> AppSerializableResult<Vec<HistoryEntry>> {
+    history::list(&app_handle).map_err(Into::into)
+}
+
+/// Returns the history entries matching every word of the query, most recent first.
+#[tauri::command]
+pub fn search_history(
+    app_handle: AppHandle,
+    query: String,
+) -> AppSerializableResult<Vec<HistoryEntry>> {
+    history::search(&query, &app_handle).map_err(Into::into)
+}
+
+/// Opens the task of a history entry in Todoist.
+#[tauri::command]
+pub fn open_history_entry(app_handle: AppHandle, id: String) -> AppSerializableResult<()> {
+    task::open_in_todoist(&id, &app_handle).map_err(Into::into)
+}
+
+/// Captures the task of a history entry again.
+#[tauri::command]
+pub async fn recapture_history_entry(
+    app_handle: AppHandle,
+    id: String,
+) -> AppSerializableResult<TaskSubmission> {
+    task::recapture(&id, &app_handle).await.map_err(Into::into)
+}
+
+/// Removes all entries from the history of captured tasks.
+#[tauri::command]
+pub fn clear_history(app_handle: AppHandle) -> AppSerializableResult<()> {
+    history::clear(&app_handle).map_err(Into::into)
+}
+
+/// Returns the current user settings.
+#[tauri::command]
+pub fn get_settings(app_handle: AppHandle) -> AppSerializableResult<Settings> {
+    settings::get(&app_handle).map_err(Into::into)
+}
+
+/// Replaces the user settings and notifies the modules depending on them.
+#[tauri::command]
+pub fn update_settings(
+    app_handle: AppHandle,
+    settings: Settings,
+) -> AppSerializableResult<Settings> {
+    settings::update(settings, &app_handle).map_err(Into::into)
+}
+
+/// Checks if the autostart feature can be managed by the application.
 ///
-/// See: https://github.com/tauri-apps/plugins-workspace/issues/2566
+/// In Snap and Flatpak environments, it is managed by the system instead.
+#[tauri::command]
+pub fn is_autostart_supported() -> bool {
+    environment::is_running_as_snap().not() && environment::is_running_as_flatpak().not()
+}
+
+/// Returns the accelerator bound to the Quick-Add dialog, if any.
+#[tauri::command]
+pub fn get_global_shortcut(app_handle: AppHandle) -> AppSerializableResult<Option<String>> {
+    shortcut::get_global_shortcut_accelerator(&app_handle).map_err(Into::into)
+}
+
+/// Returns the accelerators bound to the actions triggered by global shortcuts.
+#[tauri::command]
+pub fn get_global_shortcut_bindings(
+    app_handle: AppHandle,
+) -> AppSerializableResult<Vec<ShortcutBinding>> {
+    shortcut::get_bindings(&app_handle).map_err(Into::into)
+}
+
+/// Returns the registration results of the global shortcuts.
+#[tauri::command]
+pub fn get_global_shortcut_statuses(app_handle: AppHandle) -> Vec<ShortcutStatus> {
+    shortcut::get_statuses(&app_handle)
+}
+
+/// Replaces the global shortcut bindings and re-registers them.
 ///
-/// TODO: Replace this implementation with Tauri Notification plugin after the issue got resolved.
-#[tauri::command]
-pub fn send_notification(title: &str, body: &str) -> AppSerializableResult<()> {
-    Command::new("notify-send")
-        .arg(title)
-        .arg(body)
-        .arg("--app-name")
-        .arg(APP_ID)
-        .arg("--hint=int:transient:1")
-        .status()
-        .context("Failed to execute notify-send command")
-        .and_then(|status| {
-            if status.success() {
-                Ok(())
-            } else {
-                Err(format_err!(
-                    "notify-send command failed with status: {status:#?}"
-                ))
-            }
-        })
+/// Accelerators that could not be registered, e.g., due to a conflict with another
+/// application, are reported in the returned statuses.
+#[tauri::command]
+pub async fn update_global_shortcut_bindings(
+    app_handle: AppHandle,
+    bindings: Vec<ShortcutBinding>,
+) -> AppSerializableResult<Vec<ShortcutStatus>> {
+    shortcut::update_bindings(bindings, &app_handle)
+        .await
+        .map_err(Into::into)
+}
+
+/// Returns the desktop environment whose keybindings can be managed, if any.
+#[tauri::command]
+pub fn get_desktop_environment() -> Option<DesktopEnvironment> {
+    keybinding::detect_desktop_environment()
+}
+
+/// Returns the keybinding installed in the settings of the desktop environment, if any.
+#[tauri::command]
+pub fn get_desktop_keybinding(
+    app_handle: AppHandle,
+) -> AppSerializableResult<Option<InstalledKeybinding>> {
+    keybinding::find_installed(&app_handle).map_err(Into::into)
+}
+
+/// Installs a keybinding for opening the Quick-Add dialog in the settings of the desktop environment.
+#[tauri::command]
+pub fn install_desktop_keybinding(
+    app_handle: AppHandle,
+) -> AppSerializableResult<InstalledKeybinding> {
+    keybinding::install(&app_handle).map_err(Into::into)
+}
+
+/// Removes the keybinding installed in the settings of the desktop environment.
+#[tauri::command]
+pub fn remove_desktop_keybinding(app_handle: AppHandle) -> AppSerializableResult<()> {
+    keybinding::remove(&app_handle).map_err(Into::into)
+}
+
+/// Returns the command line opening the Quick-Add dialog, e.g., for a shortcut set up by the user.
+#[tauri::command]
+pub fn get_quick_add_command() -> AppSerializableResult<String> {
+    keybinding::get_quick_add_command_line().map_err(Into::into)
+}
+
+/// Sends a desktop notification with optional action buttons.
+///
+/// It replaces the previous notification about the same task instead of stacking up.
+/// When an action is invoked, the `NotificationAction` event is emitted with the context.
+#[tauri::command]
+pub async fn send_notification(
+    app_handle: AppHandle,
+    title: String,
+    body: Option<String>,
+    actions: Option<Vec<NotificationAction>>,
+    context: Option<NotificationContext>,
+) -> AppSerializableResult<()> {
+    notification::send(
+        &title,
+        body.as_deref().unwrap_or_default(),
+        &actions.unwrap_or_default(),
+        context.unwrap_or_default(),
+        &app_handle,
+    )
+    .await
+    .map_err(Into::into)
+}
+
+/// Takes the task to be edited after the `Edit` action of a notification was invoked, if any.
+#[tauri::command]
+pub fn take_pending_edit(app_handle: AppHandle) -> Option<NewTask> {
+    notification::take_pending_edit(&app_handle)
+}
+
+/// Returns the state of the system keyring, as revealed by the last operation on it.
+#[tauri::command]
+pub fn get_keyring_state(app_handle: AppHandle) -> KeyringState {
+    secret_store::get_keyring_state(&app_handle)
+}
+
+/// Unlocks the system keyring, prompting the user for its password, and reads the session again.
+#[tauri::command]
+pub async fn unlock_keyring(app_handle: AppHandle) -> AppSerializableResult<()> {
+    secret_service::unlock_keyring(&app_handle)
+        .await
         .map_err(Into::into)
 }
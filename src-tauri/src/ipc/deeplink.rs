@@ -1,8 +1,10 @@
 use crate::shared::environment;
 use crate::shared::error::AppResult;
+use crate::shared::metadata::APP_ID;
 use anyhow::{format_err, Context};
 use std::fmt;
 use std::ops::Not;
+use std::process::Command;
 use tauri::AppHandle;
 use tauri_plugin_deep_link::DeepLinkExt;
 
@@ -60,11 +62,12 @@ pub fn set_up_deep_link_handling(app_handle: &AppHandle) -> AppResult<()> {
                         // Spawns an async task to handle the authentication flow
                         // without blocking the event loop.
                         tauri::async_runtime::spawn(async move {
-                            crate::external::todoist::auth::authenticate(
+                            let _ = crate::external::todoist::auth::authenticate(
                                 &owned_url,
                                 &owned_app_handle,
                             )
                             .await
+                            .inspect_err(|e| log::error!("{e:?}"));
                         });
                     }
                     _ => log::error!("Invalid URL: {url:?}"),
@@ -76,3 +79,23 @@ pub fn set_up_deep_link_handling(app_handle: &AppHandle) -> AppResult<()> {
 
     Ok(())
 }
+
+/// Checks whether a handler for the application's URL scheme is available,
+/// so that the OAuth callback can be received through a deep link.
+///
+/// Outside Snap and Flatpak, the application registers itself as the handler on startup.
+/// Inside them, the handler must be provided by the packaging, so the default handler
+/// of the desktop environment is queried instead.
+pub fn is_scheme_handler_available() -> bool {
+    if environment::is_running_as_snap().not() && environment::is_running_as_flatpak().not() {
+        return true;
+    }
+
+    Command::new("xdg-mime")
+        .args(["query", "default", &format!("x-scheme-handler/{APP_ID}")])
+        .output()
+        .inspect_err(|e| log::warn!("Failed to query the URL scheme handler: {e:?}"))
+        .is_ok_and(|output| {
+            output.status.success() && !String::from_utf8_lossy(&output.stdout).trim().is_empty()
+        })
+}
@@ -9,18 +9,55 @@ use std::fmt;
 pub enum CustomEvent {
     /// Emitted when an authentication-related action occurs, e.g., successful login, logout.
     Authentication,
+    /// Emitted when the permission scopes of the current session change, e.g., after the user
+    /// authorized additional ones, with the granted scopes.
+    Scopes,
+    /// Emitted when the authentication flow fails, e.g., the user denied access, with a human message.
+    AuthenticationError,
+    /// Emitted when the authentication flow moves on, e.g., from waiting for the browser to
+    /// exchanging the code.
+    AuthenticationProgress,
     /// Emitted to trigger a quick add action for creating new tasks.
     QuickAdd,
-    /// Emitted when an autostart-related action occurs, e.g., enabling/disabling autostart.
-    Autostart,
+    /// Emitted when the user settings change, e.g., enabling/disabling autostart.
+    Settings,
+    /// Emitted when the number of tasks waiting in the outbox changes.
+    Outbox,
+    /// Emitted when the global shortcuts are (re-)registered, carrying the result of each binding.
+    GlobalShortcuts,
+    /// Emitted when the history of captured tasks changes.
+    History,
+    /// Emitted when the system has resumed from suspend.
+    Resume,
+    /// Emitted when the user invokes an action of a notification, e.g., "Open in Todoist".
+    NotificationAction,
+    /// Emitted when a secret is kept in the encrypted fallback store because the selected
+    /// secret store failed, with the error.
+    SecretStorageFallback,
+    /// Emitted when the state of the system keyring changes, e.g., when it gets locked or unlocked.
+    KeyringState,
+    /// Emitted when the general store or one of its values cannot be read and is discarded,
+    /// with the path to the backup taken before.
+    StorageCorruption,
 }
 
 impl fmt::Display for CustomEvent {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             CustomEvent::Authentication => write!(f, "authentication"),
+            CustomEvent::Scopes => write!(f, "scopes"),
+            CustomEvent::AuthenticationError => write!(f, "authentication-error"),
+            CustomEvent::AuthenticationProgress => write!(f, "authentication-progress"),
             CustomEvent::QuickAdd => write!(f, "quick-add"),
-            CustomEvent::Autostart => write!(f, "autostart"),
+            CustomEvent::Settings => write!(f, "settings"),
+            CustomEvent::Outbox => write!(f, "outbox"),
+            CustomEvent::GlobalShortcuts => write!(f, "global-shortcuts"),
+            CustomEvent::History => write!(f, "history"),
+            CustomEvent::Resume => write!(f, "resume"),
+            CustomEvent::NotificationAction => write!(f, "notification-action"),
+            CustomEvent::SecretStorageFallback => write!(f, "secret-storage-fallback"),
+            CustomEvent::KeyringState => write!(f, "keyring-state"),
+            CustomEvent::StorageCorruption => write!(f, "storage-corruption"),
         }
     }
 }
@@ -1,10 +1,11 @@
 use crate::desktop::{cli, update, window};
+use crate::external::todoist::{auth, resources, task};
 use crate::ipc::deeplink::DeepLinkHost;
 use crate::shared::error::AppResult;
 use crate::shared::metadata::APP_ID;
-use crate::shared::storage::key::StorageKey;
+use crate::shared::storage::secret_store;
 use crate::shared::{environment, state, storage};
-use desktop::{autostart, tray};
+use desktop::{autostart, notification, power, secret_service, shortcut, tray};
 use ipc::deeplink;
 use shared::state::AppState;
 use std::ops::Not;
@@ -19,6 +20,10 @@ mod shared;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    if let Some(exit_code) = cli::run_add_client() {
+        std::process::exit(exit_code);
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_single_instance::init(
             |app_handle, argv, cwd| {
@@ -45,6 +50,7 @@ pub fn run() {
             Some(vec![&cli::MINIMIZE_ARG]),
         ))
         .plugin(tauri_plugin_cli::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_global_shortcut::Builder::default().build())
         .plugin(tauri_plugin_opener::init())
@@ -54,10 +60,26 @@ pub fn run() {
         .setup(|app| {
             let app_handle = &app.handle();
 
+            #[cfg(desktop)]
+            notification::set_up_storage_warnings(app_handle);
+            let _ = storage::general::set_up_schema_migration(app_handle)
+                .inspect_err(|e| log::error!("{e:?}"));
+            storage::secure::set_up_fallback_migration(app_handle);
             *app_handle.state::<AppState>().authenticated.lock().unwrap() =
-                storage::secure::find(StorageKey::TodoistToken, app_handle)?.is_some();
+                match storage::credentials::find(app_handle) {
+                    Ok(bundle) => bundle.is_some(),
+                    Err(e) => {
+                        log::error!("{e:?}");
+                        // The session is kept while the keyring is locked and read once unlocked.
+                        secret_store::is_keyring_locked(&e)
+                    }
+                };
 
             state::set_up_state_synchronization(app_handle);
+            task::set_up_outbox_delivery(app_handle);
+            auth::set_up_token_refresh(app_handle);
+            storage::history::set_up_history_retention(app_handle);
+            resources::set_up_resource_sync(app_handle);
             #[cfg(desktop)]
             {
                 if environment::is_running_as_snap().not()
@@ -68,11 +90,14 @@ pub fn run() {
                 if environment::is_running_as_appimage() {
                     update::set_up_updater(app_handle);
                 }
+                power::set_up_resume_detection(app_handle);
+                secret_service::set_up_unlock_detection(app_handle);
+                shortcut::set_up_global_shortcut(app_handle)?;
                 tray::set_up_tray_menu(app_handle)?;
             }
             deeplink::set_up_deep_link_handling(app_handle)?;
             window::set_up_current_window_synchronization(app_handle);
-            show_initial_window(app_handle)?;
+            handle_startup_arguments(app_handle)?;
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -82,11 +107,41 @@ pub fn run() {
             ipc::commands::is_running_as_appimage,
             ipc::commands::is_running_as_flatpak,
             ipc::commands::start_authentication,
-            ipc::commands::get_todoist_access_token,
-            ipc::commands::refresh_todoist_access_token,
+            ipc::commands::cancel_authentication,
+            ipc::commands::request_additional_scopes,
+            ipc::commands::has_scope,
+            ipc::commands::log_in_with_personal_token,
+            ipc::commands::log_out,
+            ipc::commands::add_task,
+            ipc::commands::undo_last_task,
+            ipc::commands::get_outbox_summary,
+            ipc::commands::list_outbox,
+            ipc::commands::retry_outbox_entry,
+            ipc::commands::discard_outbox_entry,
+            ipc::commands::list_projects,
+            ipc::commands::list_sections,
+            ipc::commands::list_labels,
+            ipc::commands::list_history,
+            ipc::commands::search_history,
+            ipc::commands::open_history_entry,
+            ipc::commands::recapture_history_entry,
+            ipc::commands::clear_history,
+            ipc::commands::get_settings,
+            ipc::commands::update_settings,
+            ipc::commands::is_autostart_supported,
             ipc::commands::get_global_shortcut,
+            ipc::commands::get_global_shortcut_bindings,
+            ipc::commands::get_global_shortcut_statuses,
+            ipc::commands::update_global_shortcut_bindings,
+            ipc::commands::get_desktop_environment,
+            ipc::commands::get_desktop_keybinding,
+            ipc::commands::install_desktop_keybinding,
+            ipc::commands::remove_desktop_keybinding,
             ipc::commands::get_quick_add_command,
             ipc::commands::send_notification,
+            ipc::commands::take_pending_edit,
+            ipc::commands::get_keyring_state,
+            ipc::commands::unlock_keyring,
         ])
         .run(tauri::generate_context!())
         .expect("Error while running Tauri application.");
@@ -96,6 +151,7 @@ pub fn run() {
 ///
 /// This function checks the command-line arguments of the new instance.
 /// If the new instance is an OAuth deep link or requests minimization, it does nothing.
+/// If it carries the `add` subcommand, the task is added without showing any window.
 /// Otherwise, it brings the existing instance's window to the foreground.
 fn on_another_instance_trial(
     app_handle: &AppHandle,
@@ -110,23 +166,47 @@ fn on_another_instance_trial(
     if is_oauth_deep_link || should_minimize {
         return Ok(());
     }
-    show_initial_window(app_handle)?;
+    if let Some(subcommand) = app_handle
+        .cli()
+        .matches_from(argv)
+        .ok()
+        .and_then(|matches| matches.subcommand)
+    {
+        if let Ok(cli::Subcommand::Add) = subcommand.name.as_str().try_into() {
+            cli::handle_add_subcommand(&subcommand.matches, app_handle);
+            return Ok(());
+        }
+    }
+    show_initial_window(app_handle, false)?;
 
     Ok(())
 }
 
-/// Shows the initial window based on whether the user is authenticated or not.
-fn show_initial_window(app_handle: &AppHandle) -> AppResult<()> {
-    log::info!("Showing the initial window based on whether the user is authenticated or not.");
-
-    let minimize = app_handle
-        .cli()
-        .matches()?
+/// Handles the command-line arguments the application was started with and shows the initial window.
+///
+/// The window starts minimized if requested, or if the application was started by the `add` subcommand.
+fn handle_startup_arguments(app_handle: &AppHandle) -> AppResult<()> {
+    let matches = app_handle.cli().matches()?;
+    let mut minimize = matches
         .args
         .get(&cli::Argument::Minimize.to_string())
         .map(|arg| arg.value.to_owned())
         .and_then(|value| value.as_bool())
         .unwrap_or(false);
+    if let Some(subcommand) = matches.subcommand {
+        if let Ok(cli::Subcommand::Add) = subcommand.name.as_str().try_into() {
+            cli::handle_add_subcommand(&subcommand.matches, app_handle);
+            minimize = true;
+        }
+    }
+
+    show_initial_window(app_handle, minimize)
+}
+
+/// Shows the initial window based on whether the user is authenticated or not.
+fn show_initial_window(app_handle: &AppHandle, minimize: bool) -> AppResult<()> {
+    log::info!("Showing the initial window based on whether the user is authenticated or not.");
+
     let authenticated = app_handle
         .state::<AppState>()
         .authenticated
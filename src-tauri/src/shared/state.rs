@@ -1,6 +1,11 @@
+use crate::desktop::shortcut::ShortcutStatus;
+use crate::external::todoist::auth::AuthAttempt;
+use crate::external::todoist::task::NewTask;
 use crate::ipc::events::CustomEvent;
-use std::sync::Mutex;
+use crate::shared::storage::secret_store::{KeyringState, SecretStore};
+use std::sync::{Mutex, OnceLock};
 use tauri::{AppHandle, Listener, Manager};
+use tokio::sync::Notify;
 
 /// Represents the shared application state.
 ///
@@ -9,8 +14,22 @@ use tauri::{AppHandle, Listener, Manager};
 #[derive(Default)]
 pub struct AppState {
     pub authenticated: Mutex<bool>,
-    pub csrf_state: Mutex<Option<String>>,
-    pub pkce_verifier: Mutex<Option<String>>,
+    /// The authentication flow in progress, if any.
+    pub auth_attempt: Mutex<Option<AuthAttempt>>,
+    /// Wakes up the background token refresh, e.g., after logging in or resuming from suspend.
+    pub token_refresh_notify: Notify,
+    /// Wakes up the outbox delivery, e.g., when the network is known to be back.
+    pub outbox_notify: Notify,
+    /// The registration results of the global shortcuts bound by the user.
+    pub shortcut_statuses: Mutex<Vec<ShortcutStatus>>,
+    /// The task to be edited in the Quick-Add dialog, kept until the dialog fetches it.
+    pub pending_edit: Mutex<Option<NewTask>>,
+    /// The store the secrets are kept in, selected on first use.
+    pub secret_store: OnceLock<Box<dyn SecretStore>>,
+    /// The store the secrets are kept in when the selected one fails, created on first use.
+    pub fallback_secret_store: OnceLock<Box<dyn SecretStore>>,
+    /// The state of the system keyring, as revealed by the last operation on it.
+    pub keyring_state: Mutex<KeyringState>,
 }
 
 /// Sets up listeners for application state synchronization.
@@ -26,8 +45,10 @@ pub fn set_up_state_synchronization(app_handle: &AppHandle) {
             Ok(authenticated) => {
                 let state = owned_app_handle.state::<AppState>();
                 *state.authenticated.lock().unwrap() = authenticated;
-                *state.csrf_state.lock().unwrap() = None;
-                *state.pkce_verifier.lock().unwrap() = None;
+                let attempt = state.auth_attempt.lock().unwrap().take();
+                if let Some(attempt) = attempt {
+                    attempt.abort();
+                }
             }
             Err(e) => {
                 log::error!("{:?}", e);
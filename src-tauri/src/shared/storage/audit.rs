@@ -0,0 +1,57 @@
+//! This module keeps an audit log of the security-relevant changes the application
+//! makes on its own, e.g., moving secrets between stores.
+//!
+//! Entries are persisted in their own store file, so they survive the rotation of
+//! the regular log files. Only the most recent entries are kept.
+
+use crate::shared::error::AppResult;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+/// The path to the audit store file.
+const STORE_PATH: &str = "audit.json";
+
+/// The key under which the entries are stored in the audit store.
+const ENTRIES_KEY: &str = "entries";
+
+/// The maximum number of entries kept.
+const MAX_ENTRIES: usize = 500;
+
+/// Serializes read-modify-write cycles on the audit store.
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Represents an entry of the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// The Unix timestamp (seconds) at which the entry was recorded.
+    pub recorded_at: u64,
+    /// The description of the change.
+    pub message: String,
+}
+
+/// Appends an entry describing a change to the audit log.
+pub fn record(message: &str, app_handle: &AppHandle) -> AppResult<()> {
+    log::info!("Audit: {message}");
+    let recorded_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+    let _guard = LOCK.lock().unwrap();
+    let store = app_handle.store(STORE_PATH)?;
+    let mut entries = store
+        .get(ENTRIES_KEY)
+        .map(serde_json::from_value::<Vec<AuditEntry>>)
+        .transpose()?
+        .unwrap_or_default();
+    entries.push(AuditEntry {
+        recorded_at,
+        message: message.to_owned(),
+    });
+    if entries.len() > MAX_ENTRIES {
+        entries.drain(..entries.len() - MAX_ENTRIES);
+    }
+    store.set(ENTRIES_KEY, serde_json::to_value(entries)?);
+    store.save()?;
+    Ok(())
+}
@@ -0,0 +1,263 @@
+//! This module persists the Todoist credentials as a single versioned secret.
+//!
+//! The access token, its expiry, the refresh token, the token kind and the granted
+//! scopes are serialized together into one keyring entry, so every update of the
+//! session is all-or-nothing. Credentials stored by older versions under separate
+//! keys are migrated on first read.
+
+use crate::external::todoist::sdk::{self, PermissionScope};
+use crate::shared::error::AppResult;
+use crate::shared::storage;
+use crate::shared::storage::key::StorageKey;
+use crate::shared::storage::secret_store::SecretStore;
+use crate::shared::storage::secure::SecureStorage;
+use anyhow::{ensure, format_err, Context};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+/// The version of the format the credentials are stored in.
+const VERSION: u32 = 1;
+
+/// The keys under which older versions stored the credentials.
+const LEGACY_KEYS: [StorageKey; 5] = [
+    StorageKey::TodoistToken,
+    StorageKey::TodoistRefreshToken,
+    StorageKey::TodoistTokenExpiresAt,
+    StorageKey::TodoistTokenKind,
+    StorageKey::TodoistScopes,
+];
+
+/// Serializes the accesses to the credentials, so a migration runs only once.
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Represents the kind of a Todoist token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenKind {
+    /// A short-lived access token obtained through OAuth, refreshed with a refresh token.
+    OAuth,
+    /// A personal API token copied from the Todoist settings, which never expires.
+    Personal,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::OAuth => write!(f, "oauth"),
+            TokenKind::Personal => write!(f, "personal"),
+        }
+    }
+}
+
+impl TryFrom<&str> for TokenKind {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> AppResult<Self> {
+        match value {
+            "oauth" => Ok(Self::OAuth),
+            "personal" => Ok(Self::Personal),
+            _ => Err(format_err!("Unknown token kind: {}", value)),
+        }
+    }
+}
+
+/// Represents the credentials of a Todoist session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenBundle {
+    /// The kind of the access token.
+    pub kind: TokenKind,
+    /// The access token for the Todoist API.
+    pub access_token: String,
+    /// The refresh token used to obtain a new access token. Only OAuth tokens have one.
+    pub refresh_token: Option<String>,
+    /// The Unix timestamp (seconds) at which the access token expires. Personal tokens never do.
+    pub expires_at: Option<u64>,
+    /// The permission scopes granted to the access token.
+    pub scopes: Vec<PermissionScope>,
+}
+
+/// Represents the stored form of the credentials, tagged with the format version.
+#[derive(Serialize, Deserialize)]
+struct VersionedTokenBundle {
+    version: u32,
+    #[serde(flatten)]
+    bundle: TokenBundle,
+}
+
+/// Saves the credentials, replacing the stored ones as a whole.
+pub fn set(bundle: &TokenBundle, app_handle: &AppHandle) -> AppResult<()> {
+    let _guard = LOCK.lock().unwrap();
+    write(bundle, &SecureStorage(app_handle))
+}
+
+/// Retrieves the stored credentials, migrating the ones stored by older versions.
+pub fn find(app_handle: &AppHandle) -> AppResult<Option<TokenBundle>> {
+    let _guard = LOCK.lock().unwrap();
+    let store = SecureStorage(app_handle);
+    if let Some(value) = store.find(&StorageKey::TodoistCredentials)? {
+        let stored = serde_json::from_str::<VersionedTokenBundle>(&value)
+            .context("Invalid Todoist credentials entry; please sign in again")?;
+        ensure!(
+            stored.version <= VERSION,
+            "The Todoist credentials were stored by a newer version of the application (format {})",
+            stored.version
+        );
+        return Ok(Some(stored.bundle));
+    }
+    migrate_legacy_keys(&store)
+}
+
+/// Deletes the stored credentials, including any left behind by older versions.
+pub fn delete(app_handle: &AppHandle) -> AppResult<()> {
+    let _guard = LOCK.lock().unwrap();
+    storage::secure::delete(StorageKey::TodoistCredentials, app_handle)?;
+    LEGACY_KEYS
+        .into_iter()
+        .try_for_each(|key| storage::secure::delete(key, app_handle))
+}
+
+/// Writes the credentials to the given store. The caller must hold the lock.
+fn write(bundle: &TokenBundle, store: &dyn SecretStore) -> AppResult<()> {
+    let stored = VersionedTokenBundle {
+        version: VERSION,
+        bundle: bundle.to_owned(),
+    };
+    store.set(
+        &StorageKey::TodoistCredentials,
+        &serde_json::to_string(&stored)?,
+    )
+}
+
+/// Moves the credentials stored under separate keys by older versions into a single entry.
+///
+/// The new entry is written before the legacy keys are deleted, so an interrupted
+/// migration is simply completed on the next read. The caller must hold the lock.
+fn migrate_legacy_keys(store: &dyn SecretStore) -> AppResult<Option<TokenBundle>> {
+    let Some(access_token) = store.find(&StorageKey::TodoistToken)? else {
+        return Ok(None);
+    };
+    log::info!("Migrating the Todoist credentials to a single entry...");
+
+    // Tokens stored before the kind was recorded are always OAuth tokens.
+    let kind = store
+        .find(&StorageKey::TodoistTokenKind)?
+        .map_or(Ok(TokenKind::OAuth), |kind| kind.as_str().try_into())?;
+    let refresh_token = store.find(&StorageKey::TodoistRefreshToken)?;
+    let expires_at = store
+        .find(&StorageKey::TodoistTokenExpiresAt)?
+        .map(|expires_at| expires_at.parse::<u64>())
+        .transpose()
+        .context("Invalid Todoist token expiration entry; please sign in again")?;
+    // Tokens stored before the scopes were recorded only have the `task:add` scope,
+    // which was the only one requested back then.
+    let scopes = match store.find(&StorageKey::TodoistScopes)? {
+        Some(scopes) => sdk::parse_scopes(&scopes)?,
+        None if kind == TokenKind::OAuth => vec![PermissionScope::TaskAdd],
+        None => Vec::new(),
+    };
+    let bundle = TokenBundle {
+        kind,
+        access_token,
+        refresh_token,
+        expires_at,
+        scopes,
+    };
+
+    write(&bundle, store)?;
+    for key in LEGACY_KEYS {
+        let _ = store.delete(&key).inspect_err(|e| log::warn!("{e:?}"));
+    }
+    Ok(Some(bundle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::storage::secret_store::MemorySecretStore;
+
+    #[test]
+    fn migrates_the_legacy_keys_into_a_single_entry() {
+        let store = MemorySecretStore::default();
+        store.set(&StorageKey::TodoistToken, "access").unwrap();
+        store
+            .set(&StorageKey::TodoistRefreshToken, "refresh")
+            .unwrap();
+        store
+            .set(&StorageKey::TodoistTokenExpiresAt, "1700000000")
+            .unwrap();
+        store.set(&StorageKey::TodoistTokenKind, "oauth").unwrap();
+        store
+            .set(&StorageKey::TodoistScopes, "task:add,data:delete")
+            .unwrap();
+
+        let bundle = migrate_legacy_keys(&store).unwrap().unwrap();
+        assert_eq!(bundle.kind, TokenKind::OAuth);
+        assert_eq!(bundle.access_token, "access");
+        assert_eq!(bundle.refresh_token.as_deref(), Some("refresh"));
+        assert_eq!(bundle.expires_at, Some(1_700_000_000));
+        assert_eq!(
+            bundle.scopes,
+            [PermissionScope::TaskAdd, PermissionScope::DataDelete]
+        );
+
+        for key in LEGACY_KEYS {
+            assert_eq!(store.find(&key).unwrap(), None, "{key} was not deleted");
+        }
+        let stored = store
+            .find(&StorageKey::TodoistCredentials)
+            .unwrap()
+            .unwrap();
+        let stored = serde_json::from_str::<VersionedTokenBundle>(&stored).unwrap();
+        assert_eq!(stored.version, VERSION);
+        assert_eq!(stored.bundle.access_token, "access");
+    }
+
+    #[test]
+    fn migrates_an_oauth_token_stored_before_its_kind_and_scopes() {
+        let store = MemorySecretStore::default();
+        store.set(&StorageKey::TodoistToken, "access").unwrap();
+
+        let bundle = migrate_legacy_keys(&store).unwrap().unwrap();
+        assert_eq!(bundle.kind, TokenKind::OAuth);
+        assert_eq!(bundle.scopes, [PermissionScope::TaskAdd]);
+        assert_eq!(bundle.refresh_token, None);
+        assert_eq!(bundle.expires_at, None);
+    }
+
+    #[test]
+    fn migrates_a_personal_token_without_scopes() {
+        let store = MemorySecretStore::default();
+        store.set(&StorageKey::TodoistToken, "personal").unwrap();
+        store
+            .set(&StorageKey::TodoistTokenKind, "personal")
+            .unwrap();
+
+        let bundle = migrate_legacy_keys(&store).unwrap().unwrap();
+        assert_eq!(bundle.kind, TokenKind::Personal);
+        assert!(bundle.scopes.is_empty());
+    }
+
+    #[test]
+    fn migrates_nothing_without_a_legacy_token() {
+        let store = MemorySecretStore::default();
+        assert!(migrate_legacy_keys(&store).unwrap().is_none());
+        assert_eq!(store.find(&StorageKey::TodoistCredentials).unwrap(), None);
+    }
+
+    #[test]
+    fn fails_to_migrate_an_invalid_expiry() {
+        let store = MemorySecretStore::default();
+        store.set(&StorageKey::TodoistToken, "access").unwrap();
+        store
+            .set(&StorageKey::TodoistTokenExpiresAt, "tomorrow")
+            .unwrap();
+
+        assert!(migrate_legacy_keys(&store).is_err());
+        assert_eq!(
+            store.find(&StorageKey::TodoistToken).unwrap().as_deref(),
+            Some("access")
+        );
+    }
+}
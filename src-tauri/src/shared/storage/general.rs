@@ -1,35 +1,286 @@
+//! This module provides the general store of the application, which keeps the
+//! non-sensitive values, e.g., the settings, keyed by `StorageKey`.
+//!
+//! The layout of the store is versioned. On startup, the migrations newer than the stored
+//! schema version are applied in order, after the store file has been backed up.
+//!
+//! Values that cannot be read are never taken for missing ones silently: the store file is
+//! backed up, the value is ignored until it is overwritten, and the corruption is reported
+//! once per run with the `StorageCorruption` event. The value itself is kept in the store,
+//! so a newer version of the application that wrote it can still read it.
+
+use crate::ipc::events::CustomEvent;
 use crate::shared::error::AppResult;
 use crate::shared::storage::key::StorageKey;
+use anyhow::{ensure, Context};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use tauri::AppHandle;
-use tauri_plugin_store::StoreExt;
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Runtime, Wry};
+use tauri_plugin_store::{Store, StoreExt};
 
 /// The path to the store file.
 const STORE_PATH: &str = "capturist.json";
 
+/// The version of the current layout of the store.
+///
+/// Stores written before the layout was versioned have the version `0`.
+const SCHEMA_VERSION: u32 = 1;
+
+/// The keys of the values that could not be read during this run, so they are only
+/// backed up and reported once.
+static UNREADABLE_KEYS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// The migrations of the store, in the order of their versions.
+const MIGRATIONS: [Migration; 1] = [Migration {
+    version: 1,
+    description: "Move the autostart preference into the settings",
+    migrate: migrate_autostart_into_settings,
+}];
+
+/// Represents a migration of the store to the layout of a schema version.
+struct Migration {
+    /// The schema version the migration upgrades the store to.
+    version: u32,
+    /// The description of the changes, for the logs.
+    description: &'static str,
+    /// Applies the changes to the store, without saving it.
+    migrate: fn(&Store<Wry>) -> AppResult<()>,
+}
+
+/// Represents a corrupted store file or value, as emitted with the `StorageCorruption` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageCorruption {
+    /// The key of the corrupted value, or `None` if the whole file could not be read.
+    pub key: Option<String>,
+    /// The description of the error.
+    pub error: String,
+    /// The path to the backup of the store file taken before the corrupted data was set aside.
+    pub backup_path: PathBuf,
+}
+
 /// Saves a serializable value to the store.
-pub fn set<T: Serialize>(key: StorageKey, value: T, app_handle: &AppHandle) -> AppResult<()> {
+pub fn set<T: Serialize, R: Runtime>(
+    key: StorageKey,
+    value: T,
+    app_handle: &AppHandle<R>,
+) -> AppResult<()> {
     let store = app_handle.store(STORE_PATH)?;
     let json = serde_json::to_value(value);
     store.set(key.to_string(), json?);
     store.save()?;
+    UNREADABLE_KEYS.lock().unwrap().remove(&key.to_string());
     Ok(())
 }
 
 /// Retrieves and deserialize a value from the store.
-pub fn find<T: DeserializeOwned>(key: StorageKey, app_handle: &AppHandle) -> AppResult<Option<T>> {
-    let value = app_handle
-        .store(STORE_PATH)?
-        .get(key.to_string())
-        .and_then(|value| serde_json::from_value(value).ok());
-    Ok(value)
+///
+/// If the value cannot be deserialized, it is reported and ignored, and `None` is returned.
+pub fn find<T: DeserializeOwned, R: Runtime>(
+    key: StorageKey,
+    app_handle: &AppHandle<R>,
+) -> AppResult<Option<T>> {
+    let store = app_handle.store(STORE_PATH)?;
+    let Some(value) = store.get(key.to_string()) else {
+        return Ok(None);
+    };
+    match serde_json::from_value(value) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) => {
+            if UNREADABLE_KEYS.lock().unwrap().contains(&key.to_string()) {
+                return Ok(None);
+            }
+            let error = anyhow::Error::new(e).context(format!("Invalid {key} entry"));
+            let backup_path = back_up("corrupted", app_handle)?;
+            report_corruption(Some(key), &error, backup_path, app_handle)?;
+            // The key is only marked once reported, so a failed report is retried.
+            UNREADABLE_KEYS.lock().unwrap().insert(key.to_string());
+            Ok(None)
+        }
+    }
 }
 
 /// Deletes a value from the store.
-pub fn delete(key: StorageKey, app_handle: &AppHandle) -> AppResult<()> {
+pub fn delete<R: Runtime>(key: StorageKey, app_handle: &AppHandle<R>) -> AppResult<()> {
     let store = app_handle.store(STORE_PATH)?;
-    store.delete(key.to_string());
-    store.save()?;
+    if store.delete(key.to_string()) {
+        store.save()?;
+    }
     Ok(())
 }
+
+/// Upgrades the store to the current schema version, and moves an unreadable store file
+/// out of the way instead of overwriting it on the next save.
+///
+/// This must run before anything else reads the store. If a migration fails, the store
+/// is left as it was on disk.
+pub fn set_up_schema_migration(app_handle: &AppHandle) -> AppResult<()> {
+    log::info!("Setting up the general store schema migration...");
+
+    set_aside_unreadable_file(app_handle)?;
+    let store = app_handle.store(STORE_PATH)?;
+    let version = store
+        .get(StorageKey::SchemaVersion.to_string())
+        .map(serde_json::from_value::<u32>)
+        .transpose()
+        .context("Invalid schema version of the general store")?
+        .unwrap_or(0);
+    ensure!(
+        version <= SCHEMA_VERSION,
+        "The general store was written by a newer version of the application (schema {version})"
+    );
+    if version == SCHEMA_VERSION {
+        return Ok(());
+    }
+
+    // A fresh store has nothing to migrate or back up.
+    if !store.is_empty() {
+        let backup_path = back_up(&format!("schema-v{version}"), app_handle)?;
+        log::info!("Backed up the general store to {}", backup_path.display());
+    }
+    let result = MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version > version)
+        .try_for_each(|migration| {
+            log::info!(
+                "Migrating the general store to schema {}: {}",
+                migration.version,
+                migration.description
+            );
+            (migration.migrate)(&store)?;
+            store.set(StorageKey::SchemaVersion.to_string(), migration.version);
+            Ok(())
+        })
+        .and_then(|()| Ok(store.save()?));
+    if result.is_err() {
+        // The partially migrated store must not be saved by the next write.
+        let _ = store
+            .reload_ignore_defaults()
+            .inspect_err(|e| log::error!("{e:?}"));
+    }
+    result
+}
+
+/// Moves the autostart preference stored on its own by older versions into the settings.
+fn migrate_autostart_into_settings(store: &Store<Wry>) -> AppResult<()> {
+    let Some(autostart) = store.get(StorageKey::Autostart.to_string()) else {
+        return Ok(());
+    };
+    // The missing settings fall back to their defaults when read.
+    if !store.has(StorageKey::Settings.to_string()) {
+        store.set(
+            StorageKey::Settings.to_string(),
+            json!({ "autostart": autostart }),
+        );
+    }
+    store.delete(StorageKey::Autostart.to_string());
+    Ok(())
+}
+
+/// Backs up the store file and moves it away if it cannot be parsed, so the application
+/// starts with an empty store instead of overwriting the file.
+fn set_aside_unreadable_file(app_handle: &AppHandle) -> AppResult<()> {
+    let path = tauri_plugin_store::resolve_store_path(app_handle, STORE_PATH)?;
+    if !path.exists() {
+        return Ok(());
+    }
+    let error = match serde_json::from_slice::<serde_json::Map<String, serde_json::Value>>(
+        &fs::read(&path)?,
+    ) {
+        Ok(_) => return Ok(()),
+        Err(e) => anyhow::Error::new(e).context("The general store file cannot be read"),
+    };
+    let backup_path = back_up("unreadable", app_handle)?;
+    fs::remove_file(&path)?;
+    // The store may have been loaded already, without the unreadable content.
+    app_handle.store(STORE_PATH)?.clear();
+    report_corruption(None, &error, backup_path, app_handle)
+}
+
+/// Copies the store file next to itself, with the given label and the current time
+/// in the file name, and returns the path to the copy.
+fn back_up<R: Runtime>(label: &str, app_handle: &AppHandle<R>) -> AppResult<PathBuf> {
+    let path = tauri_plugin_store::resolve_store_path(app_handle, STORE_PATH)?;
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let backup_path = path.with_file_name(format!("{STORE_PATH}.{label}-{timestamp}.bak"));
+    if path.exists() {
+        fs::copy(&path, &backup_path).with_context(|| {
+            format!(
+                "Failed to back up the general store to {}",
+                backup_path.display()
+            )
+        })?;
+    }
+    Ok(backup_path)
+}
+
+/// Logs a corruption of the store and emits it with the `StorageCorruption` event.
+fn report_corruption<R: Runtime>(
+    key: Option<StorageKey>,
+    error: &anyhow::Error,
+    backup_path: PathBuf,
+    app_handle: &AppHandle<R>,
+) -> AppResult<()> {
+    log::error!(
+        "{error:?}\nThe corrupted data was ignored; a backup was saved to {}",
+        backup_path.display()
+    );
+    app_handle.emit(
+        &CustomEvent::StorageCorruption.to_string(),
+        StorageCorruption {
+            key: key.map(|key| key.to_string()),
+            error: format!("{error:#}"),
+            backup_path,
+        },
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use tauri::test::{mock_builder, mock_context, noop_assets};
+    use tauri::Listener;
+
+    #[test]
+    fn keeps_and_reports_an_unreadable_value_once() {
+        let app = mock_builder()
+            .plugin(tauri_plugin_store::Builder::default().build())
+            .build(mock_context(noop_assets()))
+            .unwrap();
+        let app_handle = app.handle().to_owned();
+
+        let (sender, receiver) = mpsc::channel();
+        app_handle.listen(CustomEvent::StorageCorruption.to_string(), move |event| {
+            let _ = sender.send(event.payload().to_owned());
+        });
+
+        set(
+            StorageKey::Settings,
+            json!({ "unknown": true }),
+            &app_handle,
+        )
+        .unwrap();
+        assert!(find::<u32, _>(StorageKey::Settings, &app_handle)
+            .unwrap()
+            .is_none());
+        assert!(find::<u32, _>(StorageKey::Settings, &app_handle)
+            .unwrap()
+            .is_none());
+
+        let store = app_handle.store(STORE_PATH).unwrap();
+        assert_eq!(
+            store.get(StorageKey::Settings.to_string()),
+            Some(json!({ "unknown": true }))
+        );
+        assert!(receiver.recv_timeout(Duration::from_secs(5)).is_ok());
+        assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+}
@@ -0,0 +1,315 @@
+//! This module keeps a local history of the captured tasks, so the user can look
+//! them up, open them in Todoist, or capture them again.
+//!
+//! Entries are persisted in their own store file and pruned according to the
+//! retention policy in the settings whenever the history is written.
+
+use crate::external::todoist::sdk::Task;
+use crate::external::todoist::task::NewTask;
+use crate::ipc::events::CustomEvent;
+use crate::shared::error::AppResult;
+use crate::shared::storage::settings::{self, Settings};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Listener, Runtime};
+use tauri_plugin_store::StoreExt;
+
+/// The path to the history store file.
+const STORE_PATH: &str = "history.json";
+
+/// The key under which the entries are stored in the history store.
+const ENTRIES_KEY: &str = "entries";
+
+/// The maximum number of entries kept, regardless of the retention period.
+const MAX_ENTRIES: usize = 1000;
+
+/// The number of seconds in a day.
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// The number of seconds after its capture during which a task can be undone.
+const UNDO_PERIOD_SECS: u64 = 15 * 60;
+
+/// Serializes read-modify-write cycles on the history store.
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Represents the send status of a captured task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureStatus {
+    /// The task was created in Todoist.
+    Sent,
+    /// The task is waiting in the outbox.
+    Queued,
+    /// The task could not be created.
+    Failed,
+    /// The task was created and then deleted again by the user.
+    Undone,
+}
+
+/// Represents a captured task in the history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// The unique ID of the entry, which is the request ID the task was sent with.
+    pub id: String,
+    /// The task as it was captured.
+    pub task: NewTask,
+    /// The Unix timestamp (seconds) at which the task was captured.
+    pub captured_at: u64,
+    /// The send status of the task.
+    pub status: CaptureStatus,
+    /// The ID of the created task.
+    pub task_id: Option<String>,
+    /// The URL of the created task in the Todoist web app.
+    pub task_url: Option<String>,
+    /// The ID of the project the task was created in.
+    pub project_id: Option<String>,
+    /// The error of the last failed attempt to send the task.
+    pub error: Option<String>,
+    /// Whether the task was created right when it was captured, rather than delivered
+    /// later from the outbox. Only such tasks can be undone.
+    #[serde(default)]
+    pub interactive: bool,
+}
+
+/// Prunes the history on startup and whenever the retention policy changes.
+pub fn set_up_history_retention(app_handle: &AppHandle) {
+    log::info!("Setting up history retention...");
+
+    let _ = apply_retention(app_handle).inspect_err(|e| log::error!("{e:?}"));
+    let retention_days = settings::get(app_handle)
+        .map(|settings| settings.history_retention_days)
+        .inspect_err(|e| log::error!("{e:?}"))
+        .ok();
+    let retention_days = Mutex::new(retention_days);
+    let owned_app_handle = app_handle.to_owned();
+    app_handle.listen(CustomEvent::Settings.to_string(), move |event| {
+        let _ = serde_json::from_str::<Settings>(event.payload())
+            .context("Failed to deserialize settings event")
+            .and_then(|settings| {
+                let current = Some(settings.history_retention_days);
+                let previous = std::mem::replace(&mut *retention_days.lock().unwrap(), current);
+                if previous == current {
+                    return Ok(());
+                }
+                apply_retention(&owned_app_handle)
+            })
+            .inspect_err(|e| log::error!("{e:?}"));
+    });
+}
+
+/// Records a newly captured task, or updates the status of an already recorded one.
+///
+/// A task recorded as sent right away is considered captured interactively.
+pub fn record<R: Runtime>(
+    id: &str,
+    task: &NewTask,
+    result: Result<&Task, &anyhow::Error>,
+    status: CaptureStatus,
+    app_handle: &AppHandle<R>,
+) -> AppResult<()> {
+    let captured_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    modify(app_handle, |entries| {
+        let position = entries.iter().position(|entry| entry.id == id);
+        let entry = match position {
+            Some(position) => &mut entries[position],
+            None => {
+                entries.push(HistoryEntry {
+                    id: id.to_owned(),
+                    task: task.to_owned(),
+                    captured_at,
+                    status,
+                    task_id: None,
+                    task_url: None,
+                    project_id: task.project_id.to_owned(),
+                    error: None,
+                    interactive: status == CaptureStatus::Sent,
+                });
+                entries.last_mut().unwrap()
+            }
+        };
+        entry.status = status;
+        match result {
+            Ok(created_task) => {
+                entry.task_id = Some(created_task.id.to_owned());
+                entry.task_url = Some(created_task.url.to_owned());
+                entry.project_id = Some(created_task.project_id.to_owned());
+                entry.error = None;
+            }
+            Err(e) => entry.error = Some(e.to_string()),
+        }
+    })
+}
+
+/// Marks the entry of a created task as undone.
+pub fn mark_undone<R: Runtime>(task_id: &str, app_handle: &AppHandle<R>) -> AppResult<()> {
+    modify(app_handle, |entries| {
+        entries
+            .iter_mut()
+            .filter(|entry| entry.task_id.as_deref() == Some(task_id))
+            .for_each(|entry| entry.status = CaptureStatus::Undone);
+    })
+}
+
+/// Retrieves all entries of the history, most recent first.
+pub fn list<R: Runtime>(app_handle: &AppHandle<R>) -> AppResult<Vec<HistoryEntry>> {
+    let _guard = LOCK.lock().unwrap();
+    let mut entries = read(app_handle)?;
+    entries.reverse();
+    Ok(entries)
+}
+
+/// Retrieves the entries of the tasks that can still be undone, most recent first.
+///
+/// These are the tasks captured interactively within the undo period, that were created
+/// and have not been undone yet.
+pub fn list_undoable<R: Runtime>(app_handle: &AppHandle<R>) -> AppResult<Vec<HistoryEntry>> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let oldest_undoable = now.saturating_sub(UNDO_PERIOD_SECS);
+    let entries = list(app_handle)?
+        .into_iter()
+        .filter(|entry| {
+            entry.interactive
+                && entry.status == CaptureStatus::Sent
+                && entry.task_id.is_some()
+                && entry.captured_at >= oldest_undoable
+        })
+        .collect();
+    Ok(entries)
+}
+
+/// Retrieves the entries matching every word of the query, most recent first.
+///
+/// The words are matched case-insensitively against the content, the description,
+/// the labels and the due date of the captured tasks.
+pub fn search<R: Runtime>(query: &str, app_handle: &AppHandle<R>) -> AppResult<Vec<HistoryEntry>> {
+    let words = query
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>();
+    let entries = list(app_handle)?
+        .into_iter()
+        .filter(|entry| {
+            let text = [
+                Some(entry.task.content.as_str()),
+                entry.task.description.as_deref(),
+                entry.task.due.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .chain(entry.task.labels.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join("\n")
+            .to_lowercase();
+            words.iter().all(|word| text.contains(word))
+        })
+        .collect();
+    Ok(entries)
+}
+
+/// Retrieves an entry by its ID.
+pub fn find<R: Runtime>(id: &str, app_handle: &AppHandle<R>) -> AppResult<Option<HistoryEntry>> {
+    let _guard = LOCK.lock().unwrap();
+    Ok(read(app_handle)?.into_iter().find(|entry| entry.id == id))
+}
+
+/// Removes all entries from the history.
+pub fn clear<R: Runtime>(app_handle: &AppHandle<R>) -> AppResult<()> {
+    modify(app_handle, Vec::clear)
+}
+
+/// Removes the entries that exceed the retention policy.
+fn apply_retention<R: Runtime>(app_handle: &AppHandle<R>) -> AppResult<()> {
+    modify(app_handle, |_| {})
+}
+
+/// Reads the entries from the store, oldest first. The caller must hold the lock.
+fn read<R: Runtime>(app_handle: &AppHandle<R>) -> AppResult<Vec<HistoryEntry>> {
+    let entries = app_handle
+        .store(STORE_PATH)?
+        .get(ENTRIES_KEY)
+        .map(serde_json::from_value)
+        .transpose()?
+        .unwrap_or_default();
+    Ok(entries)
+}
+
+/// Applies a modification to the stored entries, prunes them according to the
+/// retention policy, saves the store to disk and broadcasts the change.
+fn modify<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    f: impl FnOnce(&mut Vec<HistoryEntry>),
+) -> AppResult<()> {
+    let retention_days = settings::get(app_handle)?.history_retention_days;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+    {
+        let _guard = LOCK.lock().unwrap();
+        let mut entries = read(app_handle)?;
+        f(&mut entries);
+        if retention_days > 0 {
+            let oldest_kept = now.saturating_sub(u64::from(retention_days) * SECS_PER_DAY);
+            entries.retain(|entry| entry.captured_at >= oldest_kept);
+        }
+        if entries.len() > MAX_ENTRIES {
+            entries.drain(..entries.len() - MAX_ENTRIES);
+        }
+        let store = app_handle.store(STORE_PATH)?;
+        store.set(ENTRIES_KEY, serde_json::to_value(entries)?);
+        store.save()?;
+    }
+    // The listeners run within `emit` and may read the history, so the lock must be released.
+    app_handle.emit(&CustomEvent::History.to_string(), ())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+    use tauri::test::{mock_builder, mock_context, noop_assets};
+
+    #[test]
+    fn records_while_a_history_listener_lists_the_entries() {
+        let app = mock_builder()
+            .plugin(tauri_plugin_store::Builder::default().build())
+            .build(mock_context(noop_assets()))
+            .unwrap();
+        let app_handle = app.handle().to_owned();
+
+        let (sender, receiver) = mpsc::channel();
+        let listening_app_handle = app_handle.to_owned();
+        app_handle.listen(CustomEvent::History.to_string(), move |_| {
+            let _ = sender.send(list(&listening_app_handle).map(|entries| entries.len()));
+        });
+
+        // The task is recorded on another thread, so that a deadlock fails the test
+        // instead of hanging it.
+        thread::spawn(move || {
+            let task = NewTask {
+                content: "Buy milk".to_owned(),
+                description: None,
+                project_id: None,
+                labels: Vec::new(),
+                priority: None,
+                due: None,
+            };
+            let error = anyhow::anyhow!("The network is unreachable");
+            record(
+                "entry",
+                &task,
+                Err(&error),
+                CaptureStatus::Queued,
+                &app_handle,
+            )
+        });
+
+        let listed = receiver
+            .recv_timeout(Duration::from_secs(5))
+            .expect("The history listener did not get to list the entries");
+        assert_eq!(listed.unwrap(), 1);
+    }
+}
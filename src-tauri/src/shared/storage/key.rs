@@ -1,25 +1,51 @@
 use std::fmt;
 
 /// Represents the keys used for storing and retrieving values in various ways.
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum StorageKey {
+    /// The credentials of the Todoist session, stored together as a versioned JSON document.
+    TodoistCredentials,
     /// The access token for the Todoist API.
+    ///
+    /// Superseded by `TodoistCredentials`, like the other `Todoist*` keys; only read to
+    /// migrate the credentials of older versions.
     TodoistToken,
     /// The refresh token used to obtain a new Todoist access token.
     TodoistRefreshToken,
     /// The Unix timestamp (seconds) at which the current access token expires.
     TodoistTokenExpiresAt,
+    /// The kind of the stored Todoist token, i.e. an OAuth or a personal API token.
+    TodoistTokenKind,
+    /// The comma-separated permission scopes granted to the stored Todoist token.
+    TodoistScopes,
     /// A boolean indicating whether the application should autostart.
+    ///
+    /// Superseded by `Settings`; only read by the migration of the general store to schema 1.
     Autostart,
+    /// The user settings.
+    Settings,
+    /// The accelerators bound to the actions triggered by global shortcuts.
+    GlobalShortcuts,
+    /// The keybinding installed in the settings of the desktop environment, with the changes made.
+    DesktopKeybinding,
+    /// The version of the layout of the general store.
+    SchemaVersion,
 }
 
 impl fmt::Display for StorageKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            StorageKey::TodoistCredentials => write!(f, "TODOIST_CREDENTIALS"),
             StorageKey::TodoistToken => write!(f, "TODOIST_TOKEN"),
             StorageKey::TodoistRefreshToken => write!(f, "TODOIST_REFRESH_TOKEN"),
             StorageKey::TodoistTokenExpiresAt => write!(f, "TODOIST_TOKEN_EXPIRES_AT"),
+            StorageKey::TodoistTokenKind => write!(f, "TODOIST_TOKEN_KIND"),
+            StorageKey::TodoistScopes => write!(f, "TODOIST_SCOPES"),
             StorageKey::Autostart => write!(f, "AUTOSTART"),
+            StorageKey::Settings => write!(f, "SETTINGS"),
+            StorageKey::GlobalShortcuts => write!(f, "GLOBAL_SHORTCUTS"),
+            StorageKey::DesktopKeybinding => write!(f, "DESKTOP_KEYBINDING"),
+            StorageKey::SchemaVersion => write!(f, "SCHEMA_VERSION"),
         }
     }
 }
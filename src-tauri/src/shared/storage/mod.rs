@@ -1,6 +1,13 @@
 //! This module contains the storage-related utilities for the application.
 //! It includes modules for managing keys, keyring, and application settings.
 
+pub mod audit;
+pub mod credentials;
 pub mod general;
+pub mod history;
 pub mod key;
+pub mod outbox;
+pub mod resources;
+pub mod secret_store;
 pub mod secure;
+pub mod settings;
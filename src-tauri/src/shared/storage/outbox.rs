@@ -0,0 +1,152 @@
+//! This module provides a durable outbox for captured tasks that could not be sent yet.
+//!
+//! Entries are persisted in their own store file, so they survive restarts and
+//! are only removed once Todoist has accepted them or the user discards them.
+//! Entries that failed permanently are kept until the user retries or discards them.
+
+use crate::external::todoist::task::NewTask;
+use crate::shared::error::AppResult;
+use rand::RngExt;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+/// The path to the outbox store file.
+const STORE_PATH: &str = "outbox.json";
+
+/// The key under which the entries are stored in the outbox store.
+const ENTRIES_KEY: &str = "entries";
+
+/// Serializes read-modify-write cycles on the outbox store.
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Represents a captured task waiting in the outbox.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    /// The unique ID of the entry, also sent as the request ID so that
+    /// Todoist ignores duplicates of an already delivered entry.
+    pub id: String,
+    /// The task to be created.
+    pub task: NewTask,
+    /// The Unix timestamp (seconds) at which the task was captured.
+    pub queued_at: u64,
+    /// The number of failed delivery attempts.
+    pub attempts: u32,
+    /// The error of the last failed delivery attempt.
+    pub last_error: Option<String>,
+    /// Whether the delivery failed permanently, so it is no longer retried.
+    pub failed: bool,
+}
+
+/// Represents the number of entries in the outbox, as reported to the frontend.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct OutboxSummary {
+    /// The number of entries waiting to be delivered.
+    pub pending: usize,
+    /// The number of entries whose delivery failed permanently.
+    pub failed: usize,
+}
+
+/// Generates a new unique ID for an outbox entry.
+pub fn new_entry_id() -> String {
+    rand::rng()
+        .sample_iter(&rand::distr::Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+/// Adds a task to the outbox, already marked as failed if it cannot be delivered as is.
+pub fn push(
+    id: &str,
+    task: &NewTask,
+    error: &anyhow::Error,
+    failed: bool,
+    app_handle: &AppHandle,
+) -> AppResult<OutboxEntry> {
+    let entry = OutboxEntry {
+        id: id.to_owned(),
+        task: task.to_owned(),
+        queued_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        attempts: 1,
+        last_error: Some(error.to_string()),
+        failed,
+    };
+    modify(app_handle, |entries| entries.push(entry.to_owned()))?;
+    Ok(entry)
+}
+
+/// Retrieves all entries of the outbox in the order they were queued.
+pub fn list(app_handle: &AppHandle) -> AppResult<Vec<OutboxEntry>> {
+    let _guard = LOCK.lock().unwrap();
+    read(app_handle)
+}
+
+/// Records a failed delivery attempt of an entry.
+pub fn record_failure(
+    id: &str,
+    error: &anyhow::Error,
+    permanent: bool,
+    app_handle: &AppHandle,
+) -> AppResult<()> {
+    modify(app_handle, |entries| {
+        if let Some(entry) = entries.iter_mut().find(|entry| entry.id == id) {
+            entry.attempts += 1;
+            entry.last_error = Some(error.to_string());
+            entry.failed = permanent;
+        }
+    })
+}
+
+/// Marks a failed entry as pending again, so it is retried on the next delivery.
+///
+/// Returns whether the entry exists.
+pub fn mark_pending(id: &str, app_handle: &AppHandle) -> AppResult<bool> {
+    let mut found = false;
+    modify(app_handle, |entries| {
+        if let Some(entry) = entries.iter_mut().find(|entry| entry.id == id) {
+            entry.failed = false;
+            found = true;
+        }
+    })?;
+    Ok(found)
+}
+
+/// Removes an entry from the outbox, e.g., once it has been delivered.
+pub fn remove(id: &str, app_handle: &AppHandle) -> AppResult<()> {
+    modify(app_handle, |entries| entries.retain(|entry| entry.id != id))
+}
+
+/// Counts the pending and failed entries of the outbox.
+pub fn summarize(app_handle: &AppHandle) -> AppResult<OutboxSummary> {
+    let entries = list(app_handle)?;
+    let failed = entries.iter().filter(|entry| entry.failed).count();
+    Ok(OutboxSummary {
+        pending: entries.len() - failed,
+        failed,
+    })
+}
+
+/// Reads the entries from the store. The caller must hold the lock.
+fn read(app_handle: &AppHandle) -> AppResult<Vec<OutboxEntry>> {
+    let entries = app_handle
+        .store(STORE_PATH)?
+        .get(ENTRIES_KEY)
+        .map(serde_json::from_value)
+        .transpose()?
+        .unwrap_or_default();
+    Ok(entries)
+}
+
+/// Applies a modification to the stored entries and saves the store to disk.
+fn modify(app_handle: &AppHandle, f: impl FnOnce(&mut Vec<OutboxEntry>)) -> AppResult<()> {
+    let _guard = LOCK.lock().unwrap();
+    let mut entries = read(app_handle)?;
+    f(&mut entries);
+    let store = app_handle.store(STORE_PATH)?;
+    store.set(ENTRIES_KEY, serde_json::to_value(entries)?);
+    store.save()?;
+    Ok(())
+}
@@ -0,0 +1,74 @@
+//! This module caches the Todoist projects, sections and labels on disk, so they can
+//! be listed without hitting the network every time.
+//!
+//! The cache is kept in its own store file, along with the sync token to update it
+//! incrementally.
+
+use crate::external::todoist::sdk::{self, Label, Project, Section};
+use crate::shared::error::AppResult;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+/// The path to the resource cache store file.
+const STORE_PATH: &str = "resources.json";
+
+/// The key under which the cache is stored in the resource cache store.
+const CACHE_KEY: &str = "cache";
+
+/// Represents the cached resources of the user's Todoist account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourceCache {
+    /// The token of the last sync, to receive only the changes on the next one.
+    pub sync_token: String,
+    /// The Unix timestamp (seconds) of the last sync, or `0` if it never happened.
+    pub synced_at: u64,
+    pub projects: Vec<Project>,
+    pub sections: Vec<Section>,
+    pub labels: Vec<Label>,
+}
+
+impl Default for ResourceCache {
+    fn default() -> Self {
+        Self {
+            sync_token: sdk::FULL_SYNC_TOKEN.to_owned(),
+            synced_at: 0,
+            projects: Vec::new(),
+            sections: Vec::new(),
+            labels: Vec::new(),
+        }
+    }
+}
+
+/// Retrieves the cached resources, or an empty cache if they were never synced.
+///
+/// An unreadable cache is logged and taken for an empty one, so the next sync is a full
+/// one and replaces it.
+pub fn get(app_handle: &AppHandle) -> AppResult<ResourceCache> {
+    let Some(value) = app_handle.store(STORE_PATH)?.get(CACHE_KEY) else {
+        return Ok(ResourceCache::default());
+    };
+    let cache = serde_json::from_value(value)
+        .context("Invalid resource cache; Syncing the resources again")
+        .inspect_err(|e| log::error!("{e:?}"))
+        .unwrap_or_default();
+    Ok(cache)
+}
+
+/// Replaces the cached resources.
+pub fn set(cache: &ResourceCache, app_handle: &AppHandle) -> AppResult<()> {
+    let store = app_handle.store(STORE_PATH)?;
+    store.set(CACHE_KEY, serde_json::to_value(cache)?);
+    store.save()?;
+    Ok(())
+}
+
+/// Removes the cached resources, e.g., because they belong to a user who logged out.
+pub fn clear(app_handle: &AppHandle) -> AppResult<()> {
+    let store = app_handle.store(STORE_PATH)?;
+    store.delete(CACHE_KEY);
+    store.save()?;
+    Ok(())
+}
@@ -0,0 +1,630 @@
+//! This module abstracts over the places the secrets of the application can be kept in.
+//!
+//! The system keyring is used by default. Machines without a Secret Service daemon,
+//! e.g., headless or CI machines, can keep the secrets in an encrypted file or only in
+//! memory instead. The store is selected once at startup, with the `CAPTURIST_SECRET_STORE`
+//! environment variable taking precedence over the `secret_store` setting.
+//!
+//! Whenever the selected store fails, the encrypted file serves as the fallback.
+//!
+//! The failures of the keyring are classified into the states they reveal, e.g., a
+//! locked keyring, so a temporarily unusable keyring is not mistaken for a missing secret.
+
+use crate::ipc::events::CustomEvent;
+use crate::shared::error::AppResult;
+use crate::shared::metadata::APP_ID;
+use crate::shared::state::AppState;
+use crate::shared::storage::key::StorageKey;
+use crate::shared::storage::settings;
+use anyhow::{ensure, format_err, Context};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, KeyInit, Nonce};
+use rand::RngExt;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::{env, fmt};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_store::StoreExt;
+
+/// The environment variable overriding the secret store selected in the settings.
+const SECRET_STORE_ENV_VAR: &str = "CAPTURIST_SECRET_STORE";
+
+/// The environment variable holding an optional passphrase the encryption key is derived from.
+const SECRET_PASSPHRASE_ENV_VAR: &str = "CAPTURIST_SECRET_PASSPHRASE";
+
+/// The service name the secrets are stored under in the system keyring.
+const KEYRING_SERVICE_NAME: &str = APP_ID;
+
+/// The path to the store file of the encrypted secrets.
+const ENCRYPTED_STORE_PATH: &str = "secrets.json";
+
+/// The key under which the salt of the key derivation is stored in the encrypted store.
+const SALT_KEY: &str = "salt";
+
+/// The length of the salt of the key derivation, in bytes.
+const SALT_LENGTH: usize = 16;
+
+/// The length of the nonce prepended to every encrypted secret, in bytes.
+const NONCE_LENGTH: usize = 12;
+
+/// The number of PBKDF2 rounds used to derive the encryption key.
+const KEY_DERIVATION_ROUNDS: u32 = 100_000;
+
+/// Represents a place the secrets of the application can be kept in.
+pub trait SecretStore: Send + Sync {
+    /// Returns the kind of this store.
+    fn kind(&self) -> SecretStoreKind;
+
+    /// Saves a secret associated with a given `StorageKey`.
+    fn set(&self, key: &StorageKey, value: &str) -> AppResult<()>;
+
+    /// Retrieves the secret associated with a given `StorageKey`, if any.
+    fn find(&self, key: &StorageKey) -> AppResult<Option<String>>;
+
+    /// Deletes the secret associated with a given `StorageKey`.
+    ///
+    /// If the secret does not exist, this function will still return `Ok(())`.
+    fn delete(&self, key: &StorageKey) -> AppResult<()>;
+}
+
+/// Represents the kinds of secret stores to choose from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SecretStoreKind {
+    /// The system keyring, e.g., the Secret Service on Linux.
+    #[default]
+    Keyring,
+    /// A file in the application data directory, encrypted with a key bound to the machine.
+    EncryptedFile,
+    /// The memory of the running process, so nothing outlives it.
+    Memory,
+}
+
+impl fmt::Display for SecretStoreKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretStoreKind::Keyring => write!(f, "keyring"),
+            SecretStoreKind::EncryptedFile => write!(f, "encrypted-file"),
+            SecretStoreKind::Memory => write!(f, "memory"),
+        }
+    }
+}
+
+impl TryFrom<&str> for SecretStoreKind {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> AppResult<Self> {
+        match value {
+            "keyring" => Ok(Self::Keyring),
+            "encrypted-file" => Ok(Self::EncryptedFile),
+            "memory" => Ok(Self::Memory),
+            _ => Err(format_err!("Unknown secret store: {}", value)),
+        }
+    }
+}
+
+/// Represents the state of the system keyring, as revealed by the last operation on it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyringState {
+    /// The keyring can be used.
+    #[default]
+    Available,
+    /// The keyring is locked, or the user dismissed the prompt to unlock it.
+    Locked,
+    /// There is no Secret Service provider or D-Bus session, e.g., on a headless machine.
+    NoSecretService,
+    /// The access to the keyring has been denied, e.g., by a sandbox or by the user.
+    AccessDenied,
+}
+
+impl fmt::Display for KeyringState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyringState::Available => write!(f, "available"),
+            KeyringState::Locked => write!(f, "locked"),
+            KeyringState::NoSecretService => write!(f, "no-secret-service"),
+            KeyringState::AccessDenied => write!(f, "access-denied"),
+        }
+    }
+}
+
+/// Represents a failure of the keyring that reveals its state.
+#[derive(Debug)]
+pub struct KeyringError {
+    pub state: KeyringState,
+    source: keyring::Error,
+}
+
+impl fmt::Display for KeyringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.state {
+            KeyringState::Available => write!(f, "The keyring failed"),
+            KeyringState::Locked => write!(f, "The keyring is locked"),
+            KeyringState::NoSecretService => write!(f, "No Secret Service is available"),
+            KeyringState::AccessDenied => write!(f, "The access to the keyring has been denied"),
+        }
+    }
+}
+
+impl std::error::Error for KeyringError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Checks whether an error has been caused by a locked keyring, which is temporary.
+pub fn is_keyring_locked(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause
+            .downcast_ref::<KeyringError>()
+            .is_some_and(|e| e.state == KeyringState::Locked)
+    })
+}
+
+/// Returns the state of the keyring, as revealed by the last operation on it.
+pub fn get_keyring_state(app_handle: &AppHandle) -> KeyringState {
+    *app_handle.state::<AppState>().keyring_state.lock().unwrap()
+}
+
+/// Records the state of the keyring and broadcasts its changes with the `KeyringState` event.
+///
+/// Once a locked keyring becomes available again, the operations that failed meanwhile,
+/// i.e., the outbox delivery and the token refresh, are retried right away.
+fn set_keyring_state(state: KeyringState, app_handle: &AppHandle) -> AppResult<()> {
+    let app_state = app_handle.state::<AppState>();
+    let previous_state = std::mem::replace(&mut *app_state.keyring_state.lock().unwrap(), state);
+    if previous_state == state {
+        return Ok(());
+    }
+
+    log::info!("The keyring is {state}.");
+    if previous_state == KeyringState::Locked && state == KeyringState::Available {
+        app_state.outbox_notify.notify_one();
+        app_state.token_refresh_notify.notify_one();
+    }
+    app_handle.emit(&CustomEvent::KeyringState.to_string(), state)?;
+    Ok(())
+}
+
+/// Returns the secret store of the application, selecting it on first use.
+pub fn get(app_handle: &AppHandle) -> &dyn SecretStore {
+    app_handle
+        .state::<AppState>()
+        .inner()
+        .secret_store
+        .get_or_init(|| create(app_handle))
+        .as_ref()
+}
+
+/// Returns the store used when the selected one fails, which is the encrypted file.
+pub fn get_fallback(app_handle: &AppHandle) -> &dyn SecretStore {
+    app_handle
+        .state::<AppState>()
+        .inner()
+        .fallback_secret_store
+        .get_or_init(|| Box::new(EncryptedFileSecretStore::new(app_handle)))
+        .as_ref()
+}
+
+/// Creates the secret store selected by the environment or the settings.
+///
+/// An invalid selection falls back to the system keyring.
+fn create(app_handle: &AppHandle) -> Box<dyn SecretStore> {
+    let kind = get_selected_kind(app_handle).unwrap_or_else(|e| {
+        log::error!("{e:?}\nUsing the keyring as the secret store.");
+        SecretStoreKind::Keyring
+    });
+    log::info!("Using the {kind} secret store.");
+    match kind {
+        SecretStoreKind::Keyring => Box::new(KeyringSecretStore::new(app_handle)),
+        SecretStoreKind::EncryptedFile => Box::new(EncryptedFileSecretStore::new(app_handle)),
+        SecretStoreKind::Memory => Box::new(MemorySecretStore::default()),
+    }
+}
+
+/// Returns the kind of secret store selected by the environment or, if unset, by the settings.
+fn get_selected_kind(app_handle: &AppHandle) -> AppResult<SecretStoreKind> {
+    match env::var(SECRET_STORE_ENV_VAR) {
+        Ok(value) => value
+            .as_str()
+            .try_into()
+            .with_context(|| format!("Invalid {SECRET_STORE_ENV_VAR} environment variable")),
+        Err(_) => Ok(settings::get(app_handle)?.secret_store),
+    }
+}
+
+/// Keeps the secrets in the system keyring, tracking its state.
+struct KeyringSecretStore {
+    app_handle: AppHandle,
+}
+
+impl KeyringSecretStore {
+    fn new(app_handle: &AppHandle) -> Self {
+        Self {
+            app_handle: app_handle.to_owned(),
+        }
+    }
+
+    /// Runs an operation on the keyring entry of the given key and records the state
+    /// of the keyring revealed by its result.
+    fn run<T>(
+        &self,
+        key: &StorageKey,
+        operation: impl FnOnce(&keyring::Entry) -> Result<T, keyring::Error>,
+    ) -> AppResult<T> {
+        let result = keyring::Entry::new(KEYRING_SERVICE_NAME, &key.to_string())
+            .and_then(|entry| operation(&entry));
+        let state = match &result {
+            Ok(_) => Some(KeyringState::Available),
+            Err(e) => classify_keyring_error(e),
+        };
+        if let Some(state) = state {
+            let _ =
+                set_keyring_state(state, &self.app_handle).inspect_err(|e| log::error!("{e:?}"));
+        }
+        result.map_err(|e| match classify_keyring_error(&e) {
+            Some(state) => KeyringError { state, source: e }.into(),
+            None => e.into(),
+        })
+    }
+}
+
+impl SecretStore for KeyringSecretStore {
+    fn kind(&self) -> SecretStoreKind {
+        SecretStoreKind::Keyring
+    }
+
+    fn set(&self, key: &StorageKey, value: &str) -> AppResult<()> {
+        self.run(key, |entry| entry.set_password(value))
+    }
+
+    fn find(&self, key: &StorageKey) -> AppResult<Option<String>> {
+        self.run(key, |entry| match entry.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e),
+        })
+    }
+
+    fn delete(&self, key: &StorageKey) -> AppResult<()> {
+        self.run(key, |entry| match entry.delete_credential() {
+            Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e),
+        })
+    }
+}
+
+/// Determines the state of the keyring revealed by one of its errors, if any.
+fn classify_keyring_error(error: &keyring::Error) -> Option<KeyringState> {
+    match error {
+        keyring::Error::NoStorageAccess(source) => {
+            Some(classify_platform_error(source.as_ref()).unwrap_or(KeyringState::AccessDenied))
+        }
+        keyring::Error::PlatformFailure(source) => classify_platform_error(source.as_ref()),
+        _ => None,
+    }
+}
+
+/// Determines the state of the keyring revealed by an error of the Secret Service, if any.
+#[cfg(target_os = "linux")]
+fn classify_platform_error(
+    error: &(dyn std::error::Error + Send + Sync + 'static),
+) -> Option<KeyringState> {
+    use dbus_secret_service::Error;
+
+    match error.downcast_ref::<Error>()? {
+        Error::Locked | Error::Prompt => Some(KeyringState::Locked),
+        Error::Unavailable | Error::NoResult => Some(KeyringState::NoSecretService),
+        Error::Dbus(e) => match e.name()? {
+            "org.freedesktop.DBus.Error.AccessDenied" => Some(KeyringState::AccessDenied),
+            "org.freedesktop.DBus.Error.ServiceUnknown"
+            | "org.freedesktop.DBus.Error.NoReply"
+            | "org.freedesktop.DBus.Error.NoServer" => Some(KeyringState::NoSecretService),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Determines the state of the keyring revealed by a platform error, if any.
+///
+/// Only the Secret Service reports its state in detail.
+#[cfg(not(target_os = "linux"))]
+fn classify_platform_error(
+    _error: &(dyn std::error::Error + Send + Sync + 'static),
+) -> Option<KeyringState> {
+    None
+}
+
+/// Keeps the secrets in a store file, encrypted with ChaCha20-Poly1305.
+///
+/// The key is derived from the machine ID and the ID of the user account, so the file is
+/// useless when copied to another machine or account, and from the passphrase in the
+/// `CAPTURIST_SECRET_PASSPHRASE` environment variable, if set. The key name is
+/// authenticated along with every secret, so secrets cannot be swapped between keys either.
+struct EncryptedFileSecretStore {
+    app_handle: AppHandle,
+    /// The derived encryption key, cached since its derivation is deliberately slow.
+    key: Mutex<Option<Key>>,
+    /// The encryption key of older versions, derived only to read the secrets they stored.
+    legacy_key: Mutex<Option<Key>>,
+}
+
+impl EncryptedFileSecretStore {
+    fn new(app_handle: &AppHandle) -> Self {
+        Self {
+            app_handle: app_handle.to_owned(),
+            key: Mutex::new(None),
+            legacy_key: Mutex::new(None),
+        }
+    }
+
+    /// Returns the encryption key, deriving it on first use.
+    fn get_key(&self) -> AppResult<Key> {
+        self.derive_key(&self.key, get_key_material)
+    }
+
+    /// Returns the encryption key of older versions, deriving it on first use.
+    fn get_legacy_key(&self) -> AppResult<Key> {
+        self.derive_key(&self.legacy_key, get_legacy_key_material)
+    }
+
+    /// Derives a key from the given key material and the salt, unless it is cached already.
+    fn derive_key(
+        &self,
+        cache: &Mutex<Option<Key>>,
+        get_key_material: fn() -> AppResult<String>,
+    ) -> AppResult<Key> {
+        let mut key = cache.lock().unwrap();
+        if key.is_none() {
+            let salt = self.get_salt()?;
+            let derived_key = pbkdf2::pbkdf2_hmac_array::<Sha256, 32>(
+                get_key_material()?.as_bytes(),
+                &salt,
+                KEY_DERIVATION_ROUNDS,
+            );
+            *key = Some(Key::from(derived_key));
+        }
+        Ok(key.to_owned().unwrap())
+    }
+
+    /// Returns the salt of the key derivation, generating it when the store is created.
+    fn get_salt(&self) -> AppResult<Vec<u8>> {
+        let store = self.app_handle.store(ENCRYPTED_STORE_PATH)?;
+        if let Some(salt) = store.get(SALT_KEY) {
+            return Ok(STANDARD.decode(serde_json::from_value::<String>(salt)?)?);
+        }
+        let mut salt = vec![0; SALT_LENGTH];
+        rand::rng().fill(salt.as_mut_slice());
+        store.set(SALT_KEY, STANDARD.encode(&salt));
+        store.save()?;
+        Ok(salt)
+    }
+}
+
+impl SecretStore for EncryptedFileSecretStore {
+    fn kind(&self) -> SecretStoreKind {
+        SecretStoreKind::EncryptedFile
+    }
+
+    fn set(&self, key: &StorageKey, value: &str) -> AppResult<()> {
+        let key_name = key.to_string();
+        let encrypted = encrypt_secret(&self.get_key()?, &key_name, value)?;
+
+        let store = self.app_handle.store(ENCRYPTED_STORE_PATH)?;
+        store.set(key_name, encrypted);
+        store.save()?;
+        Ok(())
+    }
+
+    fn find(&self, key: &StorageKey) -> AppResult<Option<String>> {
+        let key_name = key.to_string();
+        let Some(value) = self.app_handle.store(ENCRYPTED_STORE_PATH)?.get(&key_name) else {
+            return Ok(None);
+        };
+        let encrypted = serde_json::from_value::<String>(value)?;
+        match decrypt_secret(&self.get_key()?, &key_name, &encrypted) {
+            Ok(value) => Ok(Some(value)),
+            // Older versions bound the key to the user name in the environment instead.
+            Err(e) => match decrypt_secret(&self.get_legacy_key()?, &key_name, &encrypted) {
+                Ok(value) => {
+                    log::info!("Encrypting the secret {key_name} with the current key...");
+                    self.set(key, &value)?;
+                    Ok(Some(value))
+                }
+                Err(_) => Err(e),
+            },
+        }
+    }
+
+    fn delete(&self, key: &StorageKey) -> AppResult<()> {
+        let store = self.app_handle.store(ENCRYPTED_STORE_PATH)?;
+        if store.delete(key.to_string()) {
+            store.save()?;
+        }
+        Ok(())
+    }
+}
+
+/// Encrypts a secret stored under the given key name, returning the random nonce
+/// followed by the ciphertext, encoded in Base64.
+fn encrypt_secret(key: &Key, key_name: &str, value: &str) -> AppResult<String> {
+    let mut nonce = [0; NONCE_LENGTH];
+    rand::rng().fill(&mut nonce);
+    let payload = Payload {
+        msg: value.as_bytes(),
+        aad: key_name.as_bytes(),
+    };
+    let ciphertext = ChaCha20Poly1305::new(key)
+        .encrypt(&Nonce::from(nonce), payload)
+        .map_err(|_| format_err!("Failed to encrypt the secret {key_name}"))?;
+    Ok(STANDARD.encode([nonce.as_slice(), &ciphertext].concat()))
+}
+
+/// Decrypts a secret encrypted by `encrypt_secret` under the same key name.
+fn decrypt_secret(key: &Key, key_name: &str, encrypted: &str) -> AppResult<String> {
+    let encrypted = STANDARD.decode(encrypted)?;
+    ensure!(
+        encrypted.len() > NONCE_LENGTH,
+        "The secret {key_name} is truncated"
+    );
+    let (nonce, ciphertext) = encrypted.split_at(NONCE_LENGTH);
+    let payload = Payload {
+        msg: ciphertext,
+        aad: key_name.as_bytes(),
+    };
+    let plaintext = ChaCha20Poly1305::new(key)
+        .decrypt(&Nonce::try_from(nonce)?, payload)
+        .map_err(|_| {
+            format_err!(
+                "Failed to decrypt the secret {key_name}; it was encrypted with another key"
+            )
+        })?;
+    Ok(String::from_utf8(plaintext)?)
+}
+
+/// Returns the secret the encryption key is derived from, which is bound to the
+/// machine and the user account, and to the optional passphrase.
+fn get_key_material() -> AppResult<String> {
+    let machine_id =
+        machine_uid::get().map_err(|e| format_err!("Failed to read the machine ID: {e}"))?;
+    let account_id = get_account_id()?;
+    let key_material = match env::var(SECRET_PASSPHRASE_ENV_VAR) {
+        Ok(passphrase) => format!("{APP_ID}:{machine_id}:{account_id}:{passphrase}"),
+        Err(_) => format!("{APP_ID}:{machine_id}:{account_id}"),
+    };
+    Ok(key_material)
+}
+
+/// Returns the secret the encryption key was derived from by older versions, which
+/// took the user name from the environment.
+fn get_legacy_key_material() -> AppResult<String> {
+    let machine_id =
+        machine_uid::get().map_err(|e| format_err!("Failed to read the machine ID: {e}"))?;
+    let user_name = env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .unwrap_or_default();
+    let key_material = match env::var(SECRET_PASSPHRASE_ENV_VAR) {
+        Ok(passphrase) => format!("{APP_ID}:{machine_id}:{user_name}:{passphrase}"),
+        Err(_) => format!("{APP_ID}:{machine_id}:{user_name}"),
+    };
+    Ok(key_material)
+}
+
+/// Returns the ID of the user account the application runs as, which, unlike the
+/// environment, cannot be changed by whoever starts the application.
+#[cfg(unix)]
+fn get_account_id() -> AppResult<String> {
+    // SAFETY: `getuid` has no preconditions and always succeeds.
+    let uid = unsafe { libc::getuid() };
+    Ok(format!("uid-{uid}"))
+}
+
+/// Returns the name of the user account the application runs as, which, unlike the
+/// environment, cannot be changed by whoever starts the application.
+#[cfg(windows)]
+fn get_account_id() -> AppResult<String> {
+    use windows_sys::Win32::System::WindowsProgramming::GetUserNameW;
+
+    // The longest account name has 256 characters, followed by the null terminator.
+    let mut buffer = [0u16; 257];
+    let mut length = buffer.len() as u32;
+    // SAFETY: The buffer is valid for `length` characters, which is updated to the
+    // number of characters written, including the null terminator.
+    let succeeded = unsafe { GetUserNameW(buffer.as_mut_ptr(), &mut length) } != 0;
+    ensure!(
+        succeeded,
+        "Failed to read the account name: {}",
+        std::io::Error::last_os_error()
+    );
+    let user_name = String::from_utf16(&buffer[..(length as usize).saturating_sub(1)])?;
+    Ok(format!("user-{user_name}"))
+}
+
+/// Keeps the secrets in memory only, so they are gone when the application quits.
+#[derive(Default)]
+pub struct MemorySecretStore {
+    secrets: Mutex<HashMap<String, String>>,
+}
+
+impl SecretStore for MemorySecretStore {
+    fn kind(&self) -> SecretStoreKind {
+        SecretStoreKind::Memory
+    }
+
+    fn set(&self, key: &StorageKey, value: &str) -> AppResult<()> {
+        let mut secrets = self.secrets.lock().unwrap();
+        secrets.insert(key.to_string(), value.to_owned());
+        Ok(())
+    }
+
+    fn find(&self, key: &StorageKey) -> AppResult<Option<String>> {
+        Ok(self.secrets.lock().unwrap().get(&key.to_string()).cloned())
+    }
+
+    fn delete(&self, key: &StorageKey) -> AppResult<()> {
+        self.secrets.lock().unwrap().remove(&key.to_string());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decrypts_an_encrypted_secret() {
+        let key = Key::from([1; 32]);
+        let encrypted = encrypt_secret(&key, "TODOIST_CREDENTIALS", "secret").unwrap();
+        assert_ne!(encrypted, "secret");
+        assert_eq!(
+            decrypt_secret(&key, "TODOIST_CREDENTIALS", &encrypted).unwrap(),
+            "secret"
+        );
+    }
+
+    #[test]
+    fn fails_to_decrypt_with_another_key() {
+        let encrypted =
+            encrypt_secret(&Key::from([1; 32]), "TODOIST_CREDENTIALS", "secret").unwrap();
+        assert!(decrypt_secret(&Key::from([2; 32]), "TODOIST_CREDENTIALS", &encrypted).is_err());
+    }
+
+    #[test]
+    fn fails_to_decrypt_under_another_key_name() {
+        let key = Key::from([1; 32]);
+        let encrypted = encrypt_secret(&key, "TODOIST_CREDENTIALS", "secret").unwrap();
+        assert!(decrypt_secret(&key, "TODOIST_TOKEN", &encrypted).is_err());
+    }
+
+    #[test]
+    fn fails_to_decrypt_a_truncated_secret() {
+        let key = Key::from([1; 32]);
+        let encrypted = encrypt_secret(&key, "TODOIST_CREDENTIALS", "secret").unwrap();
+        let truncated = STANDARD.encode(&STANDARD.decode(encrypted).unwrap()[..NONCE_LENGTH]);
+        assert!(decrypt_secret(&key, "TODOIST_CREDENTIALS", &truncated).is_err());
+    }
+
+    #[test]
+    fn keeps_secrets_in_memory() {
+        let store = MemorySecretStore::default();
+        assert_eq!(store.find(&StorageKey::TodoistCredentials).unwrap(), None);
+        store
+            .set(&StorageKey::TodoistCredentials, "secret")
+            .unwrap();
+        assert_eq!(
+            store
+                .find(&StorageKey::TodoistCredentials)
+                .unwrap()
+                .as_deref(),
+            Some("secret")
+        );
+        store.delete(&StorageKey::TodoistCredentials).unwrap();
+        assert_eq!(store.find(&StorageKey::TodoistCredentials).unwrap(), None);
+    }
+}
@@ -1,50 +1,247 @@
-//! This module provides a secure way to storage sensitive information using the system's keyring.
+//! This module provides a secure way to storage sensitive information using the selected
+//! secret store, which is the system's keyring by default.
+//!
+//! If the selected store fails, the secrets are kept in the encrypted fallback store
+//! instead and the user is warned with the `SecretStorageFallback` event, unless the
+//! strict secrets setting forbids it. The secrets are moved back into the selected store
+//! on startup and once the keyring is unlocked.
+//!
+//! A locked keyring is never reported as a missing secret, since the callers would take
+//! it for a logged out session.
 
+use crate::ipc::events::CustomEvent;
 use crate::shared::error::AppResult;
-use crate::shared::metadata::APP_ID;
 use crate::shared::storage;
 use crate::shared::storage::key::StorageKey;
-use tauri::AppHandle;
+use crate::shared::storage::secret_store::{KeyringState, SecretStore, SecretStoreKind};
+use crate::shared::storage::{audit, secret_store, settings};
+use anyhow::{ensure, Context};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Listener};
 
-const KEYRING_SERVICE_NAME: &str = APP_ID;
+/// The keys of all the secrets, any of which may have been left in a fallback store.
+const SECRET_KEYS: [StorageKey; 6] = [
+    StorageKey::TodoistCredentials,
+    StorageKey::TodoistToken,
+    StorageKey::TodoistRefreshToken,
+    StorageKey::TodoistTokenExpiresAt,
+    StorageKey::TodoistTokenKind,
+    StorageKey::TodoistScopes,
+];
 
-/// Saves a value to the system keyring associated with a given `StorageKey`.
+/// The error context of a failure of the selected store when the fallback is forbidden.
+const STRICT_SECRETS_ERROR: &str = "The secret store failed and strict secrets forbid the fallback";
+
+/// Serializes the migrations of the fallback secrets.
+static MIGRATION_LOCK: Mutex<()> = Mutex::new(());
+
+/// Exposes the secure storage, including its fallback, through the `SecretStore` interface,
+/// so its users can be run against another store, e.g., the memory store.
+pub struct SecureStorage<'a>(pub &'a AppHandle);
+
+impl SecretStore for SecureStorage<'_> {
+    fn kind(&self) -> SecretStoreKind {
+        secret_store::get(self.0).kind()
+    }
+
+    fn set(&self, key: &StorageKey, value: &str) -> AppResult<()> {
+        set(*key, value, self.0)
+    }
+
+    fn find(&self, key: &StorageKey) -> AppResult<Option<String>> {
+        find(*key, self.0)
+    }
+
+    fn delete(&self, key: &StorageKey) -> AppResult<()> {
+        delete(*key, self.0)
+    }
+}
+
+/// Saves a value to the secret store associated with a given `StorageKey`.
 pub fn set(key: StorageKey, value: &str, app_handle: &AppHandle) -> AppResult<()> {
-    get_provider(&key)
-        .and_then(|provider| provider.set_password(value))
-        .inspect_err(|e| log::error!("{e:?}\nUsing insecure storage as fallback."))
-        .or(storage::general::set(key, value, app_handle))
+    match secret_store::get(app_handle).set(&key, value) {
+        Ok(()) => discard_fallback_copies(key, app_handle),
+        Err(e) if !has_fallback(app_handle) => Err(e),
+        Err(e) => {
+            if !may_fall_back(&e, app_handle)? {
+                return Err(e.context(STRICT_SECRETS_ERROR));
+            }
+            secret_store::get_fallback(app_handle).set(&key, value)?;
+            // Older versions kept the fallback secrets in plaintext.
+            storage::general::delete(key, app_handle)
+        }
+    }
 }
 
-/// Retrieves a value from the system keyring associated with a given `StorageKey`.
+/// Retrieves a value from the secret store associated with a given `StorageKey`.
 pub fn find(key: StorageKey, app_handle: &AppHandle) -> AppResult<Option<String>> {
-    match get_provider(&key).and_then(|provider| provider.get_password()) {
-        Ok(value) => Ok(Some(value)),
-        Err(keyring::Error::NoEntry) => Ok(None),
+    let store = secret_store::get(app_handle);
+    let was_locked = secret_store::get_keyring_state(app_handle) == KeyringState::Locked;
+    match store.find(&key) {
+        Ok(value) => {
+            // The keyring has just been unlocked, so the fallback copies written meanwhile,
+            // which are newer, have been moved into it.
+            if was_locked {
+                store.find(&key)
+            } else {
+                Ok(value)
+            }
+        }
+        Err(e) if !has_fallback(app_handle) => Err(e),
         Err(e) => {
-            log::error!("{e:?}\nUsing insecure storage as fallback.");
-            storage::general::find(key, app_handle)
+            if !may_fall_back(&e, app_handle)? {
+                return Err(e.context(STRICT_SECRETS_ERROR));
+            }
+            let value = match secret_store::get_fallback(app_handle).find(&key)? {
+                Some(value) => Some(value),
+                None => storage::general::find(key, app_handle)?,
+            };
+            match value {
+                None if secret_store::is_keyring_locked(&e) => Err(e),
+                value => Ok(value),
+            }
         }
     }
 }
 
-/// Deletes a value from the system keyring associated with a given `StorageKey`.
+/// Deletes a value from the secret store associated with a given `StorageKey`.
 ///
 /// If the entry does not exist, this function will still return `Ok(())`.
 pub fn delete(key: StorageKey, app_handle: &AppHandle) -> AppResult<()> {
-    match get_provider(&key)?.delete_credential() {
-        Ok(_) => Ok(()),
-        Err(keyring::Error::NoEntry) => Ok(()),
+    match secret_store::get(app_handle).delete(&key) {
+        Ok(()) => discard_fallback_copies(key, app_handle),
+        Err(e) if !has_fallback(app_handle) => Err(e),
         Err(e) => {
-            log::error!("{e:?}\nUsing insecure storage as fallback.");
-            storage::general::delete(key, app_handle)
+            if !may_fall_back(&e, app_handle)? {
+                return Err(e.context(STRICT_SECRETS_ERROR));
+            }
+            secret_store::get_fallback(app_handle).delete(&key)?;
+            storage::general::delete(key, app_handle)?;
+            // The secret would reappear once the keyring is unlocked.
+            if secret_store::is_keyring_locked(&e) {
+                return Err(e);
+            }
+            Ok(())
         }
     }
 }
 
-/// Gets a provider for the given key.
-/// In production, this is a `keyring::Entry`.
-/// In tests, this can be a mock provider.
-fn get_provider(key: &StorageKey) -> Result<keyring::Entry, keyring::Error> {
-    keyring::Entry::new(KEYRING_SERVICE_NAME, &key.to_string())
+/// Sets up the migration of the secrets left in the fallback stores, which runs on startup
+/// and again whenever the keyring is unlocked.
+pub fn set_up_fallback_migration(app_handle: &AppHandle) {
+    log::info!("Setting up fallback secrets migration...");
+
+    let keyring_state = Mutex::new(secret_store::get_keyring_state(app_handle));
+    let owned_app_handle = app_handle.to_owned();
+    app_handle.listen(CustomEvent::KeyringState.to_string(), move |event| {
+        let _ = serde_json::from_str::<KeyringState>(event.payload())
+            .context("Failed to deserialize keyring state event")
+            .and_then(|current| {
+                let previous = std::mem::replace(&mut *keyring_state.lock().unwrap(), current);
+                if previous != KeyringState::Locked || current != KeyringState::Available {
+                    return Ok(());
+                }
+                migrate_fallback_secrets(&owned_app_handle).map(|_| ())
+            })
+            .inspect_err(|e| log::error!("{e:?}"));
+    });
+    let _ = migrate_fallback_secrets(app_handle).inspect_err(|e| log::error!("{e:?}"));
+}
+
+/// Moves the secrets left in the fallback stores back into the selected store.
+///
+/// Every secret is read back from the selected store before its fallback copy is
+/// deleted, and every move is recorded in the audit log. Nothing is moved into the
+/// memory store, which would lose the secrets on exit. Returns whether any secret
+/// has been moved.
+pub fn migrate_fallback_secrets(app_handle: &AppHandle) -> AppResult<bool> {
+    // A migration in progress moves the secrets anyway.
+    let Ok(_guard) = MIGRATION_LOCK.try_lock() else {
+        return Ok(false);
+    };
+    let store = secret_store::get(app_handle);
+    if store.kind() == SecretStoreKind::Memory {
+        return Ok(false);
+    }
+
+    let mut migrated = false;
+    for key in SECRET_KEYS {
+        // The plaintext copies are moved first, since the encrypted ones superseded them.
+        if let Some(value) = storage::general::find::<String, _>(key, app_handle)? {
+            move_secret(key, &value, "plaintext fallback", store, app_handle)?;
+            storage::general::delete(key, app_handle)?;
+            migrated = true;
+        }
+        if store.kind() != SecretStoreKind::EncryptedFile {
+            let fallback_store = secret_store::get_fallback(app_handle);
+            if let Some(value) = fallback_store.find(&key)? {
+                move_secret(key, &value, "encrypted fallback", store, app_handle)?;
+                fallback_store.delete(&key)?;
+                migrated = true;
+            }
+        }
+    }
+    Ok(migrated)
+}
+
+/// Copies a secret into the given store, verifies it can be read back, and records the move.
+fn move_secret(
+    key: StorageKey,
+    value: &str,
+    source: &str,
+    store: &dyn SecretStore,
+    app_handle: &AppHandle,
+) -> AppResult<()> {
+    store.set(&key, value)?;
+    ensure!(
+        store.find(&key)?.as_deref() == Some(value),
+        "The secret {key} could not be read back from the {} store",
+        store.kind()
+    );
+    audit::record(
+        &format!(
+            "Moved the secret {key} from the {source} store into the {} store",
+            store.kind()
+        ),
+        app_handle,
+    )
+}
+
+/// Deletes the copies of a secret left in the fallback stores, which are stale once
+/// the selected store has been updated.
+///
+/// They are kept with the memory store, as they are the only ones outliving the process.
+fn discard_fallback_copies(key: StorageKey, app_handle: &AppHandle) -> AppResult<()> {
+    match secret_store::get(app_handle).kind() {
+        SecretStoreKind::Memory => return Ok(()),
+        SecretStoreKind::EncryptedFile => {}
+        SecretStoreKind::Keyring => secret_store::get_fallback(app_handle).delete(&key)?,
+    }
+    storage::general::delete(key, app_handle)
+}
+
+/// Checks whether the selected store has a fallback, i.e., it is the system keyring.
+///
+/// The encrypted file would fall back to itself, and the memory store must not leave
+/// the secrets on disk.
+fn has_fallback(app_handle: &AppHandle) -> bool {
+    secret_store::get(app_handle).kind() == SecretStoreKind::Keyring
+}
+
+/// Checks whether the fallback store may be used after the selected store failed
+/// with the given error, and warns the user if so.
+///
+/// A locked keyring is reported with the `KeyringState` event instead, since it is temporary.
+fn may_fall_back(error: &anyhow::Error, app_handle: &AppHandle) -> AppResult<bool> {
+    if settings::get(app_handle)?.strict_secrets {
+        return Ok(false);
+    }
+    log::error!("{error:?}\nUsing the encrypted fallback store.");
+    if !secret_store::is_keyring_locked(error) {
+        app_handle.emit(
+            &CustomEvent::SecretStorageFallback.to_string(),
+            format!("{error:#}"),
+        )?;
+    }
+    Ok(true)
 }
@@ -0,0 +1,100 @@
+//! This module provides the typed user settings of the application.
+//!
+//! Every change is broadcast with the `Settings` event, so the modules depending
+//! on a setting can react to it live.
+
+use crate::ipc::events::CustomEvent;
+use crate::shared::error::AppResult;
+use crate::shared::storage::key::StorageKey;
+use crate::shared::storage::secret_store::SecretStoreKind;
+use crate::shared::storage::{general, resources};
+use anyhow::ensure;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Runtime};
+
+/// Represents the user settings.
+///
+/// Missing fields fall back to their defaults, so settings stored by an older
+/// version of the application can still be read.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Whether the application is launched at startup.
+    pub autostart: bool,
+    /// Whether the global shortcuts bound by the user are registered.
+    pub global_shortcut: bool,
+    /// Whether a notification is shown after a task has been added.
+    pub notify_on_success: bool,
+    /// Whether a notification is shown when a task could not be added.
+    pub notify_on_failure: bool,
+    /// Whether captured tasks can be undone, which requires the `data:delete` scope.
+    pub undo: bool,
+    /// The number of days captured tasks are kept in the history, or `0` to keep them forever.
+    pub history_retention_days: u32,
+    /// The ID of the project captures are added to when they name no project, or `None`
+    /// for the inbox.
+    pub default_project_id: Option<String>,
+    /// The number of minutes a login waits for the browser before it expires.
+    pub authentication_timeout_minutes: u32,
+    /// The store the secrets are kept in. Changes take effect after a restart, and
+    /// the secrets of the previous store are not carried over.
+    pub secret_store: SecretStoreKind,
+    /// Whether the secrets must never be kept in the encrypted fallback store, so failures
+    /// of the selected store are reported instead.
+    pub strict_secrets: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            autostart: true,
+            global_shortcut: false,
+            notify_on_success: true,
+            notify_on_failure: true,
+            undo: false,
+            history_retention_days: 90,
+            default_project_id: None,
+            authentication_timeout_minutes: 10,
+            secret_store: SecretStoreKind::default(),
+            strict_secrets: false,
+        }
+    }
+}
+
+/// Retrieves the current settings.
+///
+/// Before the first update, the defaults are returned.
+pub fn get<R: Runtime>(app_handle: &AppHandle<R>) -> AppResult<Settings> {
+    Ok(general::find(StorageKey::Settings, app_handle)?.unwrap_or_default())
+}
+
+/// Replaces the settings and broadcasts the change.
+///
+/// A new default project must exist in the cached projects.
+pub fn update(settings: Settings, app_handle: &AppHandle) -> AppResult<Settings> {
+    log::info!("Updating settings: {settings:?}");
+
+    if let Some(project_id) = &settings.default_project_id {
+        if get(app_handle)?.default_project_id.as_ref() != Some(project_id) {
+            ensure!(
+                resources::get(app_handle)?
+                    .projects
+                    .iter()
+                    .any(|project| &project.id == project_id),
+                "The default project does not exist"
+            );
+        }
+    }
+
+    general::set(StorageKey::Settings, &settings, app_handle)?;
+    app_handle.emit(&CustomEvent::Settings.to_string(), &settings)?;
+
+    Ok(settings)
+}
+
+/// Applies a modification to the current settings and broadcasts the change.
+pub fn modify(app_handle: &AppHandle, f: impl FnOnce(&mut Settings)) -> AppResult<Settings> {
+    let mut settings = get(app_handle)?;
+    f(&mut settings);
+    update(settings, app_handle)
+}
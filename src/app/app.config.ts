@@ -9,10 +9,7 @@ import {
   provideZonelessChangeDetection,
 } from "@angular/core";
 import { provideRouter, withInMemoryScrolling, withRouterConfig } from "@angular/router";
-import { Todoist } from "@cpt/shared/external/todoist";
-import { WindowLabel } from "@cpt/shared/ipc/window-label";
 import { window } from "@tauri-apps/api";
-import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
 import { Window } from "@tauri-apps/api/window";
 import { routes } from "./app.routes";
 import { IconService } from "@cpt/shared/theme/icon-service";
@@ -23,12 +20,6 @@ export const appConfig: ApplicationConfig = {
     provideAppInitializer(() => {
       forwardConsole();
       inject(IconService).setUpMatIconRegistry();
-      switch (getCurrentWebviewWindow().label as WindowLabel) {
-        case WindowLabel.QUICK_ADD:
-          return inject(Todoist).initialize();
-        case WindowLabel.AUTHENTICATION:
-          return Promise.resolve();
-      }
     }),
     {
       provide: APP_BASE_HREF,
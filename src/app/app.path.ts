@@ -2,4 +2,5 @@ export enum AppPath {
   ROOT = "",
   LANDING = "landing",
   QUICK_ADD = "quick-add",
+  SETTINGS = "settings",
 }
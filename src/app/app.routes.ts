@@ -14,6 +14,8 @@ export const routes: Routes = [
           return "landing";
         case WindowLabel.QUICK_ADD:
           return "quick-add";
+        case WindowLabel.SETTINGS:
+          return "settings";
       }
     },
   },
@@ -38,4 +40,14 @@ export const routes: Routes = [
           : inject(Router).parseUrl(`/${AppPath.ROOT}`),
     ],
   },
+  {
+    path: AppPath.SETTINGS,
+    loadComponent: () => import("@cpt/settings/settings-page").then((x) => x.SettingsPage),
+    canMatch: [
+      (_: Route, __: Array<UrlSegment>) =>
+        (getCurrentWebviewWindow().label as WindowLabel) === WindowLabel.SETTINGS
+          ? true
+          : inject(Router).parseUrl(`/${AppPath.ROOT}`),
+    ],
+  },
 ];
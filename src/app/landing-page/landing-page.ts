@@ -1,20 +1,99 @@
 import { NgOptimizedImage } from "@angular/common";
-import { ChangeDetectionStrategy, Component, signal } from "@angular/core";
+import {
+  ChangeDetectionStrategy,
+  Component,
+  inject,
+  OnDestroy,
+  OnInit,
+  signal,
+} from "@angular/core";
+import { NonNullableFormBuilder, ReactiveFormsModule, Validators } from "@angular/forms";
 import { MatButton } from "@angular/material/button";
+import { MatFormField } from "@angular/material/form-field";
+import { MatInput } from "@angular/material/input";
+import { CommandError } from "@cpt/shared/external/todoist";
+import { IpcEvent } from "@cpt/shared/ipc/ipc-event";
 import { invoke } from "@tauri-apps/api/core";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
 
 @Component({
   selector: "cpt-landing-page",
   templateUrl: "./landing-page.html",
   styleUrls: ["./landing-page.scss"],
   changeDetection: ChangeDetectionStrategy.OnPush,
-  imports: [NgOptimizedImage, MatButton],
+  imports: [NgOptimizedImage, MatButton, MatFormField, MatInput, ReactiveFormsModule],
 })
-export class LandingPage {
+export class LandingPage implements OnInit, OnDestroy {
   protected readonly authenticationClicked = signal(false);
+  protected readonly authenticationError = signal<string | null>(null);
+  protected readonly authenticationProgress = signal<AuthProgress | null>(null);
+  protected readonly personalTokenMode = signal(false);
+  protected readonly personalTokenError = signal<string | null>(null);
+  protected readonly personalTokenControl = inject(NonNullableFormBuilder).control("", [
+    Validators.required,
+  ]);
+  protected readonly unlistenFns = Array<UnlistenFn>();
+
+  async ngOnInit() {
+    // The flow has been reset by the backend, so go back to let the user retry.
+    const errorUnlistenFn = await listen<AuthenticationError>(
+      IpcEvent.AUTHENTICATION_ERROR,
+      (event) => {
+        this.authenticationError.set(event.payload.message);
+        this.authenticationClicked.set(false);
+      },
+    );
+    this.unlistenFns.push(errorUnlistenFn);
+    const progressUnlistenFn = await listen<AuthProgress>(
+      IpcEvent.AUTHENTICATION_PROGRESS,
+      (event) => {
+        this.authenticationProgress.set(event.payload);
+        if (event.payload === "expired") {
+          this.authenticationError.set("The login expired; please try again.");
+          this.authenticationClicked.set(false);
+        }
+      },
+    );
+    this.unlistenFns.push(progressUnlistenFn);
+  }
+
+  ngOnDestroy() {
+    this.unlistenFns.forEach((unlistenFn) => unlistenFn());
+  }
+
+  async cancelAuthentication() {
+    this.authenticationClicked.set(false);
+    await invoke("cancel_authentication");
+  }
 
   async authenticate() {
+    this.authenticationError.set(null);
     this.authenticationClicked.set(true);
     await invoke("start_authentication");
   }
+
+  async logInWithPersonalToken() {
+    if (this.personalTokenControl.invalid) return;
+
+    this.personalTokenError.set(null);
+    this.personalTokenControl.disable();
+    await invoke("log_in_with_personal_token", { token: this.personalTokenControl.value })
+      .catch((error: CommandError) => this.personalTokenError.set(error.message))
+      .finally(() => this.personalTokenControl.enable());
+  }
+}
+
+/** The progress of an authentication flow. Mirrors `AuthProgress` in the backend. */
+type AuthProgress =
+  | "waiting-for-browser"
+  | "exchanging-code"
+  | "done"
+  | "failed"
+  | "cancelled"
+  | "expired";
+
+/** A failed authentication flow. Mirrors `AuthenticationError` in the backend. */
+interface AuthenticationError {
+  code: string | null;
+  message: string;
 }
@@ -1,8 +1,10 @@
 import { ChangeDetectionStrategy, Component, inject, OnDestroy, OnInit } from "@angular/core";
 import { MatDialog, MatDialogState } from "@angular/material/dialog";
 import { QuickAddDialog } from "@cpt/quick-add/quick-add-dialog";
+import { IpcEvent } from "@cpt/shared/ipc/ipc-event";
+import { InvokedAction, NativeNotification } from "@cpt/shared/ipc/native-notification";
 import { WindowLabel } from "@cpt/shared/ipc/window-label";
-import { UnlistenFn } from "@tauri-apps/api/event";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
 import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
 import { exhaustMap, from, tap } from "rxjs";
 
@@ -15,11 +17,14 @@ import { exhaustMap, from, tap } from "rxjs";
 })
 export class QuickAddContainer implements OnInit, OnDestroy {
   protected readonly dialog = inject(MatDialog);
+  protected readonly notification = inject(NativeNotification);
   protected readonly currentWebviewWindow = getCurrentWebviewWindow();
   protected readonly unlistenFns = Array<UnlistenFn>();
 
   async ngOnInit() {
     this.openQuickAdd();
+    // The window may have been opened by the `edit` action of a notification before it mounted.
+    await this.prefillPendingEdit();
 
     const quickAddUnlistenFn = await this.currentWebviewWindow.listen("tauri://move", (event) => {
       console.log(`Event ${event} received.`);
@@ -32,12 +37,28 @@ export class QuickAddContainer implements OnInit, OnDestroy {
       this.dialog.getDialogById(WindowLabel.QUICK_ADD)?.close();
     });
     this.unlistenFns.push(blurUnlistenFn);
+
+    const notificationActionUnlistenFn = await listen<InvokedAction>(
+      IpcEvent.NOTIFICATION_ACTION,
+      async (event) => {
+        if (event.payload.action !== "edit") return;
+        this.openQuickAdd();
+        await this.prefillPendingEdit();
+      },
+    );
+    this.unlistenFns.push(notificationActionUnlistenFn);
   }
 
   ngOnDestroy() {
     this.unlistenFns.forEach((unlistenFn) => unlistenFn());
   }
 
+  protected async prefillPendingEdit() {
+    const task = await this.notification.takePendingEdit();
+    if (task === null) return;
+    this.dialog.getDialogById(WindowLabel.QUICK_ADD)?.componentInstance.prefill(task);
+  }
+
   protected openQuickAdd() {
     console.log("Opening Quick-Add dialog...");
 
@@ -20,11 +20,11 @@ import { MatIcon } from "@angular/material/icon";
 import { MatInput } from "@angular/material/input";
 import { MatProgressSpinnerModule } from "@angular/material/progress-spinner";
 import { MatTooltip } from "@angular/material/tooltip";
-import { Todoist } from "@cpt/shared/external/todoist";
+import { CommandError, NewTask, TaskSubmission, Todoist } from "@cpt/shared/external/todoist";
+import { AppSettings } from "@cpt/shared/ipc/app-settings";
 import { NativeNotification } from "@cpt/shared/ipc/native-notification";
 import { invoke } from "@tauri-apps/api/core";
 import { from } from "rxjs";
-import { Task, TodoistRequestError } from "@doist/todoist-sdk";
 
 @Component({
   selector: "cpt-quick-add-dialog",
@@ -49,18 +49,22 @@ import { Task, TodoistRequestError } from "@doist/todoist-sdk";
   ],
 })
 export class QuickAddDialog {
-  protected readonly todoistApi = inject(Todoist).api;
+  protected readonly todoist = inject(Todoist);
   protected readonly notification = inject(NativeNotification);
+  protected readonly appSettings = inject(AppSettings);
   protected readonly form = inject(NonNullableFormBuilder).group({
     name: ["", Validators.required],
     description: [""],
   });
-  protected readonly shortcutCommand = toSignal(from(invoke<string>("get_quick_add_command")));
+  protected readonly shortcutCommand = toSignal(
+    from(invoke<string>("get_quick_add_command").catch(() => "capturist --quick-add")),
+  );
   protected readonly shortcutTooltipText = computed(
     () =>
       "To assign a global shortcut, " +
       `set a script shortcut in OS settings executing "${this.shortcutCommand()}" command.`,
   );
+  protected readonly defaultProjectName = toSignal(from(this.getDefaultProjectName()));
   protected readonly isAdding = signal(false);
   protected readonly taskNameTextArea = viewChild("taskNameTextArea", {
     read: ElementRef<HTMLTextAreaElement>,
@@ -77,23 +81,63 @@ export class QuickAddDialog {
     });
   }
 
+  /** Returns the name of the project tasks naming no project are added to, if not the inbox. */
+  private async getDefaultProjectName(): Promise<string | null> {
+    const projectId = (await this.appSettings.get()).default_project_id;
+    if (projectId === null) return null;
+    const projects = await this.todoist.listProjects().catch(() => []);
+    return projects.find((project) => project.id === projectId)?.name ?? null;
+  }
+
+  /** Fills the form with a task, e.g., to edit and retry one that failed to be added. */
+  prefill(task: NewTask) {
+    this.form.setValue({ name: task.content, description: task.description ?? "" });
+  }
+
   onSubmit() {
     if (this.form.invalid || this.isAdding()) return;
 
     this.isAdding.set(true);
-    this.todoistApi
-      .quickAddTask({
-        text: this.form.getRawValue().name,
-        note: this.form.getRawValue().description,
-        autoReminder: true,
-      })
-      .then(async (task: Task) => {
+    const newTask: NewTask = {
+      content: this.form.getRawValue().name,
+      description: this.form.getRawValue().description,
+    };
+    this.todoist
+      .addTask(newTask)
+      .then(async (submission: TaskSubmission) => {
         this.form.reset();
-        await this.notification.send({ title: "Task added", body: task.url });
+        const settings = await this.appSettings.get();
+        if (!settings.notify_on_success) return;
+        switch (submission.status) {
+          case "created":
+            await this.notification.send({
+              title: "Task added",
+              body: submission.task.content,
+              actions: settings.undo ? ["open-in-todoist", "undo"] : ["open-in-todoist"],
+              context: {
+                task_id: submission.task.id,
+                task_url: submission.task.url,
+                new_task: newTask,
+              },
+            });
+            break;
+          case "queued":
+            await this.notification.send({
+              title: "Task queued",
+              body: "It will be added as soon as Todoist is reachable again.",
+            });
+            break;
+        }
       })
-      .catch(async (error: TodoistRequestError) => {
-        await this.notification.send({ title: "Failed to add task", body: error.message });
+      .catch(async (error: CommandError) => {
         console.error(error);
+        if (!(await this.appSettings.get()).notify_on_failure) return;
+        await this.notification.send({
+          title: "Failed to add task",
+          body: error.message,
+          actions: ["edit"],
+          context: { new_task: newTask },
+        });
       })
       .finally(() => {
         this.isAdding.set(false);
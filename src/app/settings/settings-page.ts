@@ -0,0 +1,326 @@
+import { DatePipe } from "@angular/common";
+import {
+  ChangeDetectionStrategy,
+  Component,
+  inject,
+  OnDestroy,
+  OnInit,
+  signal,
+} from "@angular/core";
+import { MatButton } from "@angular/material/button";
+import { MatDivider } from "@angular/material/divider";
+import { MatFormField, MatLabel } from "@angular/material/form-field";
+import { MatInput } from "@angular/material/input";
+import { MatOption, MatSelect } from "@angular/material/select";
+import { MatSlideToggle, MatSlideToggleChange } from "@angular/material/slide-toggle";
+import {
+  AppSettings,
+  KeyringState,
+  SecretStoreKind,
+  Settings,
+} from "@cpt/shared/ipc/app-settings";
+import { CaptureHistory, HistoryEntry } from "@cpt/shared/ipc/capture-history";
+import { CaptureOutbox, OutboxEntry } from "@cpt/shared/ipc/capture-outbox";
+import {
+  DesktopEnvironment,
+  GlobalShortcuts,
+  InstalledKeybinding,
+  ShortcutAction,
+  ShortcutBinding,
+  ShortcutStatus,
+} from "@cpt/shared/ipc/global-shortcuts";
+import { CommandError, Project, Todoist } from "@cpt/shared/external/todoist";
+import { IpcEvent } from "@cpt/shared/ipc/ipc-event";
+import { invoke } from "@tauri-apps/api/core";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+
+@Component({
+  selector: "cpt-settings-page",
+  templateUrl: "./settings-page.html",
+  styleUrl: "./settings-page.scss",
+  changeDetection: ChangeDetectionStrategy.OnPush,
+  imports: [
+    DatePipe,
+    MatButton,
+    MatDivider,
+    MatSlideToggle,
+    MatFormField,
+    MatLabel,
+    MatInput,
+    MatSelect,
+    MatOption,
+  ],
+})
+export class SettingsPage implements OnInit, OnDestroy {
+  protected readonly appSettings = inject(AppSettings);
+  protected readonly settings = signal<Settings | null>(null);
+  protected readonly autostartSupported = signal(false);
+  protected readonly todoist = inject(Todoist);
+  protected readonly projects = signal<Project[]>([]);
+  protected readonly defaultProjectError = signal<string | null>(null);
+  protected readonly globalShortcuts = inject(GlobalShortcuts);
+  protected readonly shortcutActions: { action: ShortcutAction; label: string }[] = [
+    { action: "quick-add", label: "Open Quick-Add" },
+    { action: "capture-clipboard", label: "Add clipboard text as a task" },
+    { action: "open-todoist", label: "Open Todoist" },
+  ];
+  protected readonly shortcutBindings = signal<ShortcutBinding[]>([]);
+  protected readonly shortcutStatuses = signal<ShortcutStatus[]>([]);
+  protected readonly shortcutError = signal<string | null>(null);
+  protected readonly desktopEnvironment = signal<DesktopEnvironment | null>(null);
+  protected readonly desktopKeybinding = signal<InstalledKeybinding | null>(null);
+  protected readonly desktopKeybindingError = signal<string | null>(null);
+  protected readonly undoAuthorized = signal(false);
+  protected readonly undoError = signal<string | null>(null);
+  protected readonly captureHistory = inject(CaptureHistory);
+  protected readonly historyEntries = signal<HistoryEntry[]>([]);
+  protected readonly historyQuery = signal("");
+  protected readonly historyError = signal<string | null>(null);
+  protected readonly captureOutbox = inject(CaptureOutbox);
+  protected readonly outboxEntries = signal<OutboxEntry[]>([]);
+  protected readonly outboxError = signal<string | null>(null);
+  protected readonly secretStorageWarning = signal<string | null>(null);
+  protected readonly keyringState = signal<KeyringState>("available");
+  protected readonly keyringError = signal<string | null>(null);
+  protected readonly unlistenFns = Array<UnlistenFn>();
+
+  async ngOnInit() {
+    this.settings.set(await this.appSettings.get());
+    this.autostartSupported.set(await invoke<boolean>("is_autostart_supported"));
+    await this.todoist
+      .listProjects()
+      .then((projects) => this.projects.set(projects))
+      .catch((error) => console.error(error));
+    this.shortcutBindings.set(await this.globalShortcuts.getBindings());
+    this.shortcutStatuses.set(await this.globalShortcuts.getStatuses());
+    this.desktopEnvironment.set(await this.globalShortcuts.getDesktopEnvironment());
+    this.desktopKeybinding.set(await this.globalShortcuts.getDesktopKeybinding());
+
+    // Keep in sync with changes made elsewhere, e.g., from the tray menu.
+    const settingsUnlistenFn = await listen<Settings>(IpcEvent.SETTINGS, (event) =>
+      this.settings.set(event.payload),
+    );
+    this.unlistenFns.push(settingsUnlistenFn);
+    const shortcutsUnlistenFn = await listen<ShortcutStatus[]>(
+      IpcEvent.GLOBAL_SHORTCUTS,
+      (event) => this.shortcutStatuses.set(event.payload),
+    );
+    this.unlistenFns.push(shortcutsUnlistenFn);
+    await this.loadUndoAuthorization();
+    const authenticationUnlistenFn = await listen(IpcEvent.AUTHENTICATION, () =>
+      this.loadUndoAuthorization(),
+    );
+    this.unlistenFns.push(authenticationUnlistenFn);
+    const scopesUnlistenFn = await listen(IpcEvent.SCOPES, () => this.loadUndoAuthorization());
+    this.unlistenFns.push(scopesUnlistenFn);
+    await this.loadHistory();
+    const historyUnlistenFn = await listen(IpcEvent.HISTORY, () => this.loadHistory());
+    this.unlistenFns.push(historyUnlistenFn);
+    await this.loadOutbox();
+    const outboxUnlistenFn = await listen(IpcEvent.OUTBOX, () => this.loadOutbox());
+    this.unlistenFns.push(outboxUnlistenFn);
+    const secretStorageUnlistenFn = await listen<string>(
+      IpcEvent.SECRET_STORAGE_FALLBACK,
+      (event) => this.secretStorageWarning.set(event.payload),
+    );
+    this.unlistenFns.push(secretStorageUnlistenFn);
+    this.keyringState.set(await invoke<KeyringState>("get_keyring_state"));
+    const keyringUnlistenFn = await listen<KeyringState>(IpcEvent.KEYRING_STATE, (event) =>
+      this.keyringState.set(event.payload),
+    );
+    this.unlistenFns.push(keyringUnlistenFn);
+  }
+
+  ngOnDestroy() {
+    this.unlistenFns.forEach((unlistenFn) => unlistenFn());
+  }
+
+  protected async toggle(key: keyof Settings, change: MatSlideToggleChange) {
+    const settings = this.settings();
+    if (settings === null) return;
+
+    await this.appSettings
+      .update({ ...settings, [key]: change.checked })
+      .then((updated) => this.settings.set(updated))
+      .catch((error) => {
+        change.source.checked = !change.checked;
+        console.error(error);
+      });
+  }
+
+  protected async setHistoryRetention(days: string) {
+    const settings = this.settings();
+    const retentionDays = Number.parseInt(days, 10);
+    if (settings === null || !Number.isInteger(retentionDays) || retentionDays < 0) return;
+
+    await this.appSettings
+      .update({ ...settings, history_retention_days: retentionDays })
+      .then((updated) => this.settings.set(updated))
+      .catch((error) => console.error(error));
+  }
+
+  protected async setDefaultProject(projectId: string | null) {
+    const settings = this.settings();
+    if (settings === null) return;
+
+    await this.appSettings
+      .update({ ...settings, default_project_id: projectId })
+      .then((updated) => {
+        this.settings.set(updated);
+        this.defaultProjectError.set(null);
+      })
+      .catch((error: CommandError) => {
+        this.defaultProjectError.set(error.message);
+        console.error(error);
+      });
+  }
+
+  protected async setSecretStore(secretStore: SecretStoreKind) {
+    const settings = this.settings();
+    if (settings === null) return;
+
+    await this.appSettings
+      .update({ ...settings, secret_store: secretStore })
+      .then((updated) => this.settings.set(updated))
+      .catch((error) => console.error(error));
+  }
+
+  protected async unlockKeyring() {
+    await invoke("unlock_keyring")
+      .then(() => this.keyringError.set(null))
+      .catch((error: CommandError) => {
+        this.keyringError.set(error.message);
+        console.error(error);
+      });
+  }
+
+  protected async searchHistory(query: string) {
+    this.historyQuery.set(query);
+    await this.loadHistory();
+  }
+
+  protected async openHistoryEntry(entry: HistoryEntry) {
+    await this.captureHistory.open(entry.id).catch((error: CommandError) => {
+      this.historyError.set(error.message);
+      console.error(error);
+    });
+  }
+
+  protected async recaptureHistoryEntry(entry: HistoryEntry) {
+    await this.captureHistory
+      .recapture(entry.id)
+      .then(() => this.historyError.set(null))
+      .catch((error: CommandError) => {
+        this.historyError.set(error.message);
+        console.error(error);
+      });
+  }
+
+  protected async clearHistory() {
+    await this.captureHistory.clear().catch((error) => console.error(error));
+  }
+
+  /** Lists the history entries matching the current search query. */
+  private async loadHistory() {
+    const query = this.historyQuery().trim();
+    await (query === "" ? this.captureHistory.list() : this.captureHistory.search(query))
+      .then((entries) => this.historyEntries.set(entries))
+      .catch((error) => console.error(error));
+  }
+
+  protected async retryOutboxEntry(entry: OutboxEntry) {
+    await this.captureOutbox
+      .retry(entry.id)
+      .then(() => this.outboxError.set(null))
+      .catch((error: CommandError) => {
+        this.outboxError.set(error.message);
+        console.error(error);
+      });
+  }
+
+  protected async discardOutboxEntry(entry: OutboxEntry) {
+    await this.captureOutbox
+      .discard(entry.id)
+      .then(() => this.outboxError.set(null))
+      .catch((error: CommandError) => {
+        this.outboxError.set(error.message);
+        console.error(error);
+      });
+  }
+
+  private async loadOutbox() {
+    await this.captureOutbox
+      .list()
+      .then((entries) => this.outboxEntries.set(entries))
+      .catch((error) => console.error(error));
+  }
+
+  /** Re-runs the authentication flow to grant the permission needed for undoing tasks. */
+  protected async authorizeUndo() {
+    await invoke("request_additional_scopes", { scopes: ["data:delete"] }).catch(
+      (error: CommandError) => {
+        this.undoError.set(error.message);
+        console.error(error);
+      },
+    );
+  }
+
+  private async loadUndoAuthorization() {
+    await invoke<boolean>("has_scope", { scope: "data:delete" })
+      .then((granted) => this.undoAuthorized.set(granted))
+      .catch(() => this.undoAuthorized.set(false));
+  }
+
+  protected getAccelerator(action: ShortcutAction): string {
+    return this.shortcutBindings().find((binding) => binding.action === action)?.accelerator ?? "";
+  }
+
+  protected getShortcutError(action: ShortcutAction): string | null {
+    return this.shortcutStatuses().find((status) => status.action === action)?.error ?? null;
+  }
+
+  /** Binds an accelerator to an action, or unbinds the action if the accelerator is empty. */
+  protected async bindShortcut(action: ShortcutAction, accelerator: string) {
+    const bindings = this.shortcutBindings().filter((binding) => binding.action !== action);
+    if (accelerator.trim() !== "") {
+      bindings.push({ action, accelerator: accelerator.trim() });
+    }
+
+    await this.globalShortcuts
+      .updateBindings(bindings)
+      .then(() => {
+        this.shortcutBindings.set(bindings);
+        this.shortcutError.set(null);
+      })
+      .catch((error: CommandError) => {
+        this.shortcutError.set(error.message);
+        console.error(error);
+      });
+  }
+
+  protected async installDesktopKeybinding() {
+    await this.globalShortcuts
+      .installDesktopKeybinding()
+      .then((keybinding) => {
+        this.desktopKeybinding.set(keybinding);
+        this.desktopKeybindingError.set(null);
+      })
+      .catch((error: CommandError) => {
+        this.desktopKeybindingError.set(error.message);
+        console.error(error);
+      });
+  }
+
+  protected async removeDesktopKeybinding() {
+    await this.globalShortcuts
+      .removeDesktopKeybinding()
+      .then(() => {
+        this.desktopKeybinding.set(null);
+        this.desktopKeybindingError.set(null);
+      })
+      .catch((error: CommandError) => {
+        this.desktopKeybindingError.set(error.message);
+        console.error(error);
+      });
+  }
+}
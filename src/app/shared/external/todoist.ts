@@ -1,90 +1,99 @@
 import { Injectable } from "@angular/core";
-import { TodoistApi, TodoistRequestError } from "@doist/todoist-sdk";
 import { invoke } from "@tauri-apps/api/core";
 
 /**
- * Wraps the Todoist SDK client so every API call goes through a single point
- * that refreshes an expired access token and retries once.
+ * Creates Todoist tasks and lists the user's projects and labels through the backend.
  *
- * Only the short-lived access token is exposed here (fetched from the backend
- * keyring via IPC); the long-lived refresh token is never sent to the webview.
+ * All API requests, including refreshing an expired access token, are made by
+ * the backend, so no token is ever exposed to the webview.
  */
 @Injectable({ providedIn: "root" })
 export class Todoist {
-  /** The underlying SDK client. `undefined` until {@link initialize} runs. */
-  private apiClient!: TodoistApi;
-
-  /** In-flight refresh, deduplicating concurrent refreshes. `null` when idle. */
-  private refreshing: Promise<void> | null = null;
-
   /**
-   * A single proxy over the API client, so {@link api} has a stable identity
-   * and every method call is routed through {@link call}.
+   * Adds a new task. If only the content and the description are given, the
+   * content is parsed with Todoist's quick-add syntax.
+   *
+   * If the task cannot be sent right now, e.g., while offline, the backend keeps
+   * it in its outbox and delivers it later.
    */
-  private readonly apiProxy = new Proxy({} as TodoistApi, {
-    get:
-      (_, prop: keyof TodoistApi) =>
-      (...args: Array<never>) =>
-        this.call(prop, args),
-  });
-
-  /** The proxied Todoist API client for making authorized requests. */
-  get api(): TodoistApi {
-    return this.apiProxy;
+  async addTask(task: NewTask): Promise<TaskSubmission> {
+    return await invoke<TaskSubmission>("add_task", { task });
   }
 
   /**
-   * Fetches a valid access token from the backend and builds the SDK client.
-   * Call once at startup (or after signing in) before using {@link api}.
+   * Lists the active projects. They are served from the backend's cache while it is
+   * fresh, so this is cheap enough to call on every keystroke.
    */
-  async initialize(): Promise<void> {
-    const token = await invoke<string>("get_todoist_access_token");
-    this.apiClient = new TodoistApi(token);
+  async listProjects(refresh = false): Promise<Project[]> {
+    return await invoke<Project[]>("list_projects", { refresh });
   }
 
-  /**
-   * Runs one method on the live client. If the call fails with a 401 the access
-   * token has expired: it refreshes (deduplicated) and retries once against the
-   * freshly rebuilt client, so the retry never reuses the stale token.
-   *
-   * Non-401 errors propagate unchanged.
-   */
-  private async call(method: keyof TodoistApi, args: Array<never>): Promise<unknown> {
-    const run = () => {
-      const client = this.apiClient;
-      const apiMethod = Reflect.get(client, method, client) as ApiMethod;
-      return apiMethod.apply(client, args);
-    };
-
-    try {
-      return await run();
-    } catch (error) {
-      // Access token expired mid-session: refresh (once, deduped) and retry
-      // against the freshly rebuilt client.
-      if (error instanceof TodoistRequestError && error.httpStatusCode === 401) {
-        console.warn("Hit error 401; Refreshing the token manually...");
-        await this.refresh();
-        return await run();
-      }
-      throw error;
-    }
+  /** Lists the active sections, optionally of a single project, from the backend's cache. */
+  async listSections(projectId?: string, refresh = false): Promise<Section[]> {
+    return await invoke<Section[]>("list_sections", { projectId, refresh });
   }
 
-  /**
-   * Refreshes the access token, deduplicating concurrent refreshes so the
-   * rotated refresh token is only ever used once (two races would revoke it).
-   */
-  private async refresh(): Promise<void> {
-    this.refreshing ??= this.doRefresh().finally(() => (this.refreshing = null));
-    await this.refreshing;
+  /** Lists the personal labels, from the backend's cache while it is fresh. */
+  async listLabels(refresh = false): Promise<Label[]> {
+    return await invoke<Label[]>("list_labels", { refresh });
   }
+}
 
-  /** Performs a single backend refresh and rebuilds the client with the new token. */
-  private async doRefresh(): Promise<void> {
-    await invoke("refresh_todoist_access_token");
-    await this.initialize();
-  }
+/** A task to be created. Mirrors `NewTask` in the backend. */
+export interface NewTask {
+  content: string;
+  description?: string;
+  project_id?: string;
+  labels?: Array<string>;
+  priority?: number;
+  due?: string;
+}
+
+/** A task as returned by the Todoist API. Mirrors `Task` in the backend. */
+export interface Task {
+  id: string;
+  content: string;
+  description: string;
+  project_id: string;
+  labels: Array<string>;
+  priority: number;
+  due: { date: string; string: string; is_recurring: boolean } | null;
+  url: string;
 }
 
-/** An SDK method taking zero or more arguments and returning a promise. */
-type ApiMethod = (...args: Array<never>) => Promise<unknown>;
+/** A project as returned by the Todoist API. Mirrors `Project` in the backend. */
+export interface Project {
+  id: string;
+  name: string;
+  parent_id: string | null;
+  color: string;
+  inbox_project: boolean;
+  child_order: number;
+}
+
+/** A section of a project. Mirrors `Section` in the backend. */
+export interface Section {
+  id: string;
+  project_id: string;
+  name: string;
+  section_order: number;
+}
+
+/** A personal label. Mirrors `Label` in the backend. */
+export interface Label {
+  id: string;
+  name: string;
+  color: string;
+  item_order: number;
+  is_favorite: boolean;
+}
+
+/** The outcome of submitting a task. Mirrors `TaskSubmission` in the backend. */
+export type TaskSubmission =
+  | { status: "created"; task: Task }
+  | { status: "queued"; entry_id: string; reason: string };
+
+/** The error returned by a failed backend command. */
+export interface CommandError {
+  message: string;
+}
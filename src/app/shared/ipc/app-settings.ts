@@ -0,0 +1,36 @@
+import { Injectable } from "@angular/core";
+import { invoke } from "@tauri-apps/api/core";
+
+/** Reads and updates the user settings stored by the backend. */
+@Injectable({
+  providedIn: "root",
+})
+export class AppSettings {
+  async get(): Promise<Settings> {
+    return await invoke<Settings>("get_settings");
+  }
+
+  async update(settings: Settings): Promise<Settings> {
+    return await invoke<Settings>("update_settings", { settings });
+  }
+}
+
+/** The user settings. Mirrors `Settings` in the backend. */
+export interface Settings {
+  autostart: boolean;
+  global_shortcut: boolean;
+  notify_on_success: boolean;
+  notify_on_failure: boolean;
+  undo: boolean;
+  history_retention_days: number;
+  default_project_id: string | null;
+  authentication_timeout_minutes: number;
+  secret_store: SecretStoreKind;
+  strict_secrets: boolean;
+}
+
+/** The places the secrets can be kept in. Mirrors `SecretStoreKind` in the backend. */
+export type SecretStoreKind = "keyring" | "encrypted-file" | "memory";
+
+/** The state of the system keyring. Mirrors `KeyringState` in the backend. */
+export type KeyringState = "available" | "locked" | "no-secret-service" | "access-denied";
@@ -0,0 +1,47 @@
+import { Injectable } from "@angular/core";
+import { NewTask, TaskSubmission } from "@cpt/shared/external/todoist";
+import { invoke } from "@tauri-apps/api/core";
+
+/** Reads and acts on the local history of captured tasks kept by the backend. */
+@Injectable({
+  providedIn: "root",
+})
+export class CaptureHistory {
+  async list(): Promise<HistoryEntry[]> {
+    return await invoke<HistoryEntry[]>("list_history");
+  }
+
+  async search(query: string): Promise<HistoryEntry[]> {
+    return await invoke<HistoryEntry[]>("search_history", { query });
+  }
+
+  async open(id: string): Promise<void> {
+    await invoke("open_history_entry", { id });
+  }
+
+  async recapture(id: string): Promise<TaskSubmission> {
+    return await invoke<TaskSubmission>("recapture_history_entry", { id });
+  }
+
+  async clear(): Promise<void> {
+    await invoke("clear_history");
+  }
+}
+
+/** Mirrors `CaptureStatus` in the backend. */
+export type CaptureStatus = "sent" | "queued" | "failed" | "undone";
+
+/** A captured task. Mirrors `HistoryEntry` in the backend. */
+export interface HistoryEntry {
+  id: string;
+  task: NewTask;
+  /** Unix timestamp in seconds. */
+  captured_at: number;
+  status: CaptureStatus;
+  task_id: string | null;
+  task_url: string | null;
+  project_id: string | null;
+  error: string | null;
+  /** Whether the task was created right when it was captured, rather than from the outbox. */
+  interactive: boolean;
+}
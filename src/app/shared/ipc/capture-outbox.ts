@@ -0,0 +1,32 @@
+import { Injectable } from "@angular/core";
+import { NewTask } from "@cpt/shared/external/todoist";
+import { invoke } from "@tauri-apps/api/core";
+
+/** Lists, retries and discards the captured tasks waiting in the backend's outbox. */
+@Injectable({
+  providedIn: "root",
+})
+export class CaptureOutbox {
+  async list(): Promise<OutboxEntry[]> {
+    return await invoke<OutboxEntry[]>("list_outbox");
+  }
+
+  async retry(id: string): Promise<void> {
+    await invoke("retry_outbox_entry", { id });
+  }
+
+  async discard(id: string): Promise<void> {
+    await invoke("discard_outbox_entry", { id });
+  }
+}
+
+/** A captured task waiting in the outbox. Mirrors `OutboxEntry` in the backend. */
+export interface OutboxEntry {
+  id: string;
+  task: NewTask;
+  /** Unix timestamp in seconds. */
+  queued_at: number;
+  attempts: number;
+  last_error: string | null;
+  failed: boolean;
+}
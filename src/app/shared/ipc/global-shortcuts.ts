@@ -0,0 +1,61 @@
+import { Injectable } from "@angular/core";
+import { invoke } from "@tauri-apps/api/core";
+
+/** Reads and updates the accelerators bound to global shortcut actions. */
+@Injectable({
+  providedIn: "root",
+})
+export class GlobalShortcuts {
+  async getBindings(): Promise<ShortcutBinding[]> {
+    return await invoke<ShortcutBinding[]>("get_global_shortcut_bindings");
+  }
+
+  async getStatuses(): Promise<ShortcutStatus[]> {
+    return await invoke<ShortcutStatus[]>("get_global_shortcut_statuses");
+  }
+
+  async updateBindings(bindings: ShortcutBinding[]): Promise<ShortcutStatus[]> {
+    return await invoke<ShortcutStatus[]>("update_global_shortcut_bindings", { bindings });
+  }
+
+  async getDesktopEnvironment(): Promise<DesktopEnvironment | null> {
+    return await invoke<DesktopEnvironment | null>("get_desktop_environment");
+  }
+
+  async getDesktopKeybinding(): Promise<InstalledKeybinding | null> {
+    return await invoke<InstalledKeybinding | null>("get_desktop_keybinding");
+  }
+
+  async installDesktopKeybinding(): Promise<InstalledKeybinding> {
+    return await invoke<InstalledKeybinding>("install_desktop_keybinding");
+  }
+
+  async removeDesktopKeybinding(): Promise<void> {
+    await invoke("remove_desktop_keybinding");
+  }
+}
+
+/** Mirrors `ShortcutAction` in the backend. */
+export type ShortcutAction = "quick-add" | "capture-clipboard" | "open-todoist";
+
+export interface ShortcutBinding {
+  action: ShortcutAction;
+  accelerator: string;
+}
+
+/** Mirrors `DesktopEnvironment` in the backend. */
+export type DesktopEnvironment = "gnome" | "kde" | "xfce" | "cinnamon";
+
+export interface InstalledKeybinding {
+  desktop_environment: DesktopEnvironment;
+  accelerator: string;
+  command: string;
+  changes: string[];
+}
+
+export interface ShortcutStatus {
+  action: ShortcutAction;
+  accelerator: string;
+  registered: boolean;
+  error: string | null;
+}
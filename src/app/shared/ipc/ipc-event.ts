@@ -1,4 +1,13 @@
 export const enum IpcEvent {
   AUTHENTICATION = "authentication",
+  AUTHENTICATION_ERROR = "authentication-error",
+  SCOPES = "scopes",
   QUICK_ADD = "quick-add",
+  OUTBOX = "outbox",
+  SETTINGS = "settings",
+  GLOBAL_SHORTCUTS = "global-shortcuts",
+  HISTORY = "history",
+  NOTIFICATION_ACTION = "notification-action",
+  SECRET_STORAGE_FALLBACK = "secret-storage-fallback",
+  KEYRING_STATE = "keyring-state",
 }
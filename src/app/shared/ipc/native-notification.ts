@@ -1,11 +1,39 @@
 import { Injectable } from "@angular/core";
+import { NewTask } from "@cpt/shared/external/todoist";
 import { invoke } from "@tauri-apps/api/core";
 
 @Injectable({
   providedIn: "root",
 })
 export class NativeNotification {
-  async send(options: { title: string; body?: string }) {
+  /** Sends a notification, replacing the previous one about the same task if it is still shown. */
+  async send(options: {
+    title: string;
+    body?: string;
+    actions?: Array<NotificationAction>;
+    context?: NotificationContext;
+  }) {
     await invoke("send_notification", options);
   }
+
+  /** Takes the task to be edited after the `edit` action of a notification was invoked, if any. */
+  async takePendingEdit(): Promise<NewTask | null> {
+    return await invoke<NewTask | null>("take_pending_edit");
+  }
+}
+
+/** Mirrors `NotificationAction` in the backend. */
+export type NotificationAction = "open-in-todoist" | "undo" | "edit";
+
+/** The task a notification is about. Mirrors `NotificationContext` in the backend. */
+export interface NotificationContext {
+  task_id?: string;
+  task_url?: string;
+  new_task?: NewTask;
+}
+
+/** The payload of the `notification-action` event. */
+export interface InvokedAction {
+  action: NotificationAction;
+  context: NotificationContext;
 }
@@ -1,4 +1,5 @@
 export const enum WindowLabel {
   QUICK_ADD = "quick-add",
   AUTHENTICATION = "authentication",
+  SETTINGS = "settings",
 }